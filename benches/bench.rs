@@ -3,7 +3,7 @@ use vorderman::round::NumbersRound;
 use vorderman::solver::find_solutions;
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let inputs = vec![
+    let inputs = [
         NumbersRound {
             target: 321,
             numbers: vec![1, 2, 3, 5, 10, 100],
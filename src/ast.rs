@@ -0,0 +1,477 @@
+use std::fmt;
+
+use num::rational::Ratio;
+
+use super::expr::{
+    Expression, FixExpressionError, Operation, Parenthesis, PostfixExpression, PrefixExpression,
+    Token, TokenKind,
+};
+
+/// An expression as a binary tree, where each operation node owns its two operands.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Num(usize),
+    BinOp {
+        op: Operation,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+/// Walks an [`Expr`], producing a value from each node. Operation nodes are handed their
+/// children so the visitor decides the traversal order.
+pub trait Visitor<T> {
+    fn visit_num(&mut self, n: usize) -> T;
+    fn visit_bin_op(&mut self, op: Operation, lhs: &Expr, rhs: &Expr) -> T;
+}
+
+/// Binding strength of an operation in infix notation; higher binds tighter.
+pub(crate) fn precedence(op: Operation) -> u8 {
+    match op {
+        Operation::Add | Operation::Subtract => 1,
+        Operation::Multiply | Operation::Divide | Operation::Modulo => 2,
+        Operation::Power => 3,
+        Operation::Concatenate => 4,
+    }
+}
+
+fn is_right_associative(op: Operation) -> bool {
+    op == Operation::Power
+}
+
+impl Expr {
+    pub fn bin_op(op: Operation, lhs: Expr, rhs: Expr) -> Expr {
+        Expr::BinOp {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    pub fn accept<T>(&self, visitor: &mut impl Visitor<T>) -> T {
+        match self {
+            Expr::Num(n) => visitor.visit_num(*n),
+            Expr::BinOp { op, lhs, rhs } => visitor.visit_bin_op(*op, lhs, rhs),
+        }
+    }
+
+    pub fn evaluate(&self) -> Result<Ratio<isize>, FixExpressionError> {
+        match self {
+            Expr::Num(n) => isize::try_from(*n)
+                .map(Ratio::from_integer)
+                .map_err(|_| FixExpressionError::Overflow),
+            Expr::BinOp { op, lhs, rhs } => {
+                if *op == Operation::Concatenate
+                    && !(matches!(**lhs, Expr::Num(_)) && matches!(**rhs, Expr::Num(_)))
+                {
+                    return Err(FixExpressionError::ConcatenatedNonTile);
+                }
+                op.checked_apply(&lhs.evaluate()?, &rhs.evaluate()?)
+            }
+        }
+    }
+
+    /// Number of operations on the longest path from the root to a number.
+    pub fn depth(&self) -> usize {
+        match self {
+            Expr::Num(_) => 0,
+            Expr::BinOp { lhs, rhs, .. } => 1 + lhs.depth().max(rhs.depth()),
+        }
+    }
+
+    /// Total number of nodes, i.e. numbers plus operations.
+    pub fn size(&self) -> usize {
+        match self {
+            Expr::Num(_) => 1,
+            Expr::BinOp { lhs, rhs, .. } => 1 + lhs.size() + rhs.size(),
+        }
+    }
+
+    /// The numbers used, from left to right.
+    pub fn tiles(&self) -> Vec<usize> {
+        match self {
+            Expr::Num(n) => vec![*n],
+            Expr::BinOp { lhs, rhs, .. } => {
+                let mut tiles = lhs.tiles();
+                tiles.extend(rhs.tiles());
+                tiles
+            }
+        }
+    }
+
+    pub fn to_postfix(&self) -> PostfixExpression {
+        let mut tokens = Vec::<Token>::new();
+        self.push_postfix(&mut tokens);
+        PostfixExpression(Expression(tokens))
+    }
+
+    fn push_postfix(&self, tokens: &mut Vec<Token>) {
+        match self {
+            Expr::Num(n) => tokens.push(TokenKind::Number(*n).into()),
+            Expr::BinOp { op, lhs, rhs } => {
+                lhs.push_postfix(tokens);
+                rhs.push_postfix(tokens);
+                tokens.push(TokenKind::Operation(*op).into());
+            }
+        }
+    }
+
+    pub fn to_prefix(&self) -> PrefixExpression {
+        let mut tokens = Vec::<Token>::new();
+        self.push_prefix(&mut tokens);
+        PrefixExpression(Expression(tokens))
+    }
+
+    fn push_prefix(&self, tokens: &mut Vec<Token>) {
+        match self {
+            Expr::Num(n) => tokens.push(TokenKind::Number(*n).into()),
+            Expr::BinOp { op, lhs, rhs } => {
+                tokens.push(TokenKind::Operation(*op).into());
+                lhs.push_prefix(tokens);
+                rhs.push_prefix(tokens);
+            }
+        }
+    }
+
+    /// Infix tokens with only the parentheses needed to preserve the tree's structure.
+    pub fn to_infix(&self) -> Expression {
+        let mut tokens = Vec::<Token>::new();
+        self.push_infix(&mut tokens);
+        Expression(tokens)
+    }
+
+    fn push_infix(&self, tokens: &mut Vec<Token>) {
+        match self {
+            Expr::Num(n) => tokens.push(TokenKind::Number(*n).into()),
+            Expr::BinOp { op, lhs, rhs } => {
+                let (lhs_parens, rhs_parens) = self.operand_needs_parentheses().unwrap();
+                push_bracketed(lhs, lhs_parens, tokens);
+                tokens.push(TokenKind::Operation(*op).into());
+                push_bracketed(rhs, rhs_parens, tokens);
+            }
+        }
+    }
+
+    /// For an operation node, whether its left and right operands must be bracketed in infix
+    /// notation. Returns `None` for numbers.
+    pub(crate) fn operand_needs_parentheses(&self) -> Option<(bool, bool)> {
+        let Expr::BinOp { op, lhs, rhs } = self else {
+            return None;
+        };
+        Some(operands_need_parentheses(*op, lhs, rhs))
+    }
+}
+
+/// Whether the left and right operands of `lhs op rhs` must be bracketed in infix notation.
+pub(crate) fn operands_need_parentheses(op: Operation, lhs: &Expr, rhs: &Expr) -> (bool, bool) {
+    let outer = precedence(op);
+    let lhs_parens = match *lhs {
+        Expr::BinOp { op: inner, .. } => {
+            precedence(inner) < outer || (precedence(inner) == outer && is_right_associative(op))
+        }
+        Expr::Num(_) => false,
+    };
+    let rhs_parens = match *rhs {
+        Expr::BinOp { op: inner, .. } => {
+            precedence(inner) < outer || (precedence(inner) == outer && !is_right_associative(op))
+        }
+        Expr::Num(_) => false,
+    };
+    (lhs_parens, rhs_parens)
+}
+
+fn push_bracketed(expr: &Expr, bracketed: bool, tokens: &mut Vec<Token>) {
+    if bracketed {
+        tokens.push(TokenKind::Parenthesis(Parenthesis::Open).into());
+    }
+    expr.push_infix(tokens);
+    if bracketed {
+        tokens.push(TokenKind::Parenthesis(Parenthesis::Close).into());
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_infix())
+    }
+}
+
+impl TryFrom<&PostfixExpression> for Expr {
+    type Error = FixExpressionError;
+
+    fn try_from(postfix: &PostfixExpression) -> Result<Expr, Self::Error> {
+        let mut stack = Vec::<Expr>::new();
+
+        for (i, token) in postfix.0 .0.iter().enumerate() {
+            match token.kind {
+                TokenKind::Number(n) => stack.push(Expr::Num(n)),
+                TokenKind::Operation(op) => {
+                    if stack.len() < 2 {
+                        return Err(FixExpressionError::StackUnderflow(i));
+                    }
+                    let rhs = stack.pop().unwrap();
+                    let lhs = stack.pop().unwrap();
+                    stack.push(Expr::bin_op(op, lhs, rhs));
+                }
+                TokenKind::Parenthesis(_) => {
+                    return Err(FixExpressionError::UnexpectedParenthesis(i))
+                }
+            }
+        }
+
+        match stack.len() {
+            0 => Err(FixExpressionError::EmptyExpression),
+            1 => Ok(stack.pop().unwrap()),
+            n => Err(FixExpressionError::LeftoverOperands(n)),
+        }
+    }
+}
+
+impl From<&Expr> for PostfixExpression {
+    fn from(expr: &Expr) -> PostfixExpression {
+        expr.to_postfix()
+    }
+}
+
+impl TryFrom<&PrefixExpression> for Expr {
+    type Error = FixExpressionError;
+
+    fn try_from(prefix: &PrefixExpression) -> Result<Expr, Self::Error> {
+        let mut stack = Vec::<Expr>::new();
+
+        // Read backwards, so each operation finds its left operand on top of the stack
+        for (i, token) in prefix.0 .0.iter().enumerate().rev() {
+            match token.kind {
+                TokenKind::Number(n) => stack.push(Expr::Num(n)),
+                TokenKind::Operation(op) => {
+                    if stack.len() < 2 {
+                        return Err(FixExpressionError::StackUnderflow(i));
+                    }
+                    let lhs = stack.pop().unwrap();
+                    let rhs = stack.pop().unwrap();
+                    stack.push(Expr::bin_op(op, lhs, rhs));
+                }
+                TokenKind::Parenthesis(_) => {
+                    return Err(FixExpressionError::UnexpectedParenthesis(i))
+                }
+            }
+        }
+
+        match stack.len() {
+            0 => Err(FixExpressionError::EmptyExpression),
+            1 => Ok(stack.pop().unwrap()),
+            n => Err(FixExpressionError::LeftoverOperands(n)),
+        }
+    }
+}
+
+impl From<&Expr> for PrefixExpression {
+    fn from(expr: &Expr) -> PrefixExpression {
+        expr.to_prefix()
+    }
+}
+
+impl TryFrom<&Expression> for Expr {
+    type Error = FixExpressionError;
+
+    /// Parse infix tokens, where `^` is right associative and every other operation is left
+    /// associative.
+    fn try_from(infix: &Expression) -> Result<Expr, Self::Error> {
+        if infix.0.is_empty() {
+            return Err(FixExpressionError::EmptyExpression);
+        }
+
+        let mut parser = InfixParser {
+            tokens: &infix.0,
+            position: 0,
+        };
+        let expr = parser.parse_expression(0)?;
+
+        match parser.peek() {
+            None => Ok(expr),
+            Some(TokenKind::Parenthesis(Parenthesis::Close)) => {
+                Err(FixExpressionError::UnmatchedParenthesis(parser.position))
+            }
+            Some(_) => Err(FixExpressionError::UnexpectedToken(parser.position)),
+        }
+    }
+}
+
+/// Precedence-climbing parser over infix tokens.
+struct InfixParser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl InfixParser<'_> {
+    fn peek(&self) -> Option<TokenKind> {
+        self.tokens.get(self.position).map(|token| token.kind)
+    }
+
+    fn parse_expression(&mut self, min_precedence: u8) -> Result<Expr, FixExpressionError> {
+        let mut lhs = self.parse_operand()?;
+
+        while let Some(TokenKind::Operation(op)) = self.peek() {
+            if precedence(op) < min_precedence {
+                break;
+            }
+            self.position += 1;
+
+            let next_min_precedence = if is_right_associative(op) {
+                precedence(op)
+            } else {
+                precedence(op) + 1
+            };
+            let rhs = self.parse_expression(next_min_precedence)?;
+            lhs = Expr::bin_op(op, lhs, rhs);
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_operand(&mut self) -> Result<Expr, FixExpressionError> {
+        let start = self.position;
+        self.position += 1;
+
+        match self.tokens.get(start).map(|token| token.kind) {
+            Some(TokenKind::Number(n)) => Ok(Expr::Num(n)),
+            Some(TokenKind::Parenthesis(Parenthesis::Open)) => {
+                let expr = self.parse_expression(0)?;
+                match self.peek() {
+                    Some(TokenKind::Parenthesis(Parenthesis::Close)) => {
+                        self.position += 1;
+                        Ok(expr)
+                    }
+                    None => Err(FixExpressionError::UnmatchedParenthesis(start)),
+                    Some(_) => Err(FixExpressionError::UnexpectedToken(self.position)),
+                }
+            }
+            Some(_) => Err(FixExpressionError::UnexpectedToken(start)),
+            None => Err(FixExpressionError::UnexpectedEnd),
+        }
+    }
+}
+
+impl From<&Expr> for Expression {
+    fn from(expr: &Expr) -> Expression {
+        expr.to_infix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn postfix(input: &str) -> PostfixExpression {
+        PostfixExpression(Expression::from_str(input).unwrap())
+    }
+
+    fn infix(input: &str) -> Expr {
+        Expr::try_from(&Expression::from_str(input).unwrap()).unwrap()
+    }
+
+    #[test_case("7", "7"; "single number")]
+    #[test_case("100 3 + 7 *", "( 100 + 3 ) * 7"; "brackets for lower precedence")]
+    #[test_case("100 3 7 * +", "100 + 3 * 7"; "no brackets for higher precedence")]
+    #[test_case("10 4 - 3 -", "10 - 4 - 3"; "left associative")]
+    #[test_case("10 4 3 - -", "10 - ( 4 - 3 )"; "right operand of same precedence")]
+    #[test_case("2 3 ^ 2 ^", "( 2 ^ 3 ) ^ 2"; "left operand of power")]
+    #[test_case("2 3 2 ^ ^", "2 ^ 3 ^ 2"; "right associative power")]
+    #[test_case("1 2 | 5 *", "1 | 2 * 5"; "concatenation binds tightest")]
+    fn postfix_to_infix_tests(input: &str, expected: &str) {
+        let expr = Expr::try_from(&postfix(input)).unwrap();
+        assert_eq!(expr.to_string(), expected);
+        assert_eq!(infix(expected), expr);
+        assert_eq!(expr.to_postfix(), postfix(input));
+    }
+
+    #[test_case("7", "7"; "single number")]
+    #[test_case("100 3 + 7 *", "* + 100 3 7"; "nested on left")]
+    #[test_case("100 3 7 * -", "- 100 * 3 7"; "nested on right")]
+    #[test_case("2 3 ^ 4 5 - /", "/ ^ 2 3 - 4 5"; "nested on both sides")]
+    fn postfix_to_prefix_tests(input: &str, expected: &str) {
+        let expr = Expr::try_from(&postfix(input)).unwrap();
+        let prefix = PrefixExpression(Expression::from_str(expected).unwrap());
+        assert_eq!(expr.to_prefix(), prefix);
+        assert_eq!(Expr::try_from(&prefix), Ok(expr));
+    }
+
+    #[test_case("", FixExpressionError::EmptyExpression; "empty")]
+    #[test_case("+ 1", FixExpressionError::StackUnderflow(0); "missing operand")]
+    #[test_case("1 2 +", FixExpressionError::StackUnderflow(2); "postfix input")]
+    #[test_case("+ 1 2 3", FixExpressionError::LeftoverOperands(2); "leftover operand")]
+    fn prefix_error_tests(input: &str, expected: FixExpressionError) {
+        let prefix = PrefixExpression(Expression::from_str(input).unwrap());
+        assert_eq!(Expr::try_from(&prefix), Err(expected));
+    }
+
+    #[test_case("1 + 2 * 3", "1 2 3 * +"; "precedence")]
+    #[test_case("(1 + 2) * 3", "1 2 + 3 *"; "parentheses")]
+    #[test_case("((4))", "4"; "redundant parentheses")]
+    #[test_case("8 / 4 / 2", "8 4 / 2 /"; "left associative division")]
+    #[test_case("2 ^ 3 ^ 2", "2 3 2 ^ ^"; "right associative power")]
+    #[test_case("25 * (3 + 7) - 100 % 6", "25 3 7 + * 100 6 % -"; "mixed operations")]
+    fn infix_to_postfix_tests(input: &str, expected: &str) {
+        assert_eq!(infix(input).to_postfix(), postfix(expected));
+    }
+
+    #[test_case("", FixExpressionError::EmptyExpression; "empty")]
+    #[test_case("1 +", FixExpressionError::UnexpectedEnd; "missing operand")]
+    #[test_case("(1 + 2", FixExpressionError::UnmatchedParenthesis(0); "unclosed parenthesis")]
+    #[test_case("1 + 2)", FixExpressionError::UnmatchedParenthesis(3); "unopened parenthesis")]
+    #[test_case("1 2 +", FixExpressionError::UnexpectedToken(1); "postfix input")]
+    #[test_case("* 1 2", FixExpressionError::UnexpectedToken(0); "prefix input")]
+    #[test_case("1 + ()", FixExpressionError::UnexpectedToken(3); "empty parentheses")]
+    fn infix_error_tests(input: &str, expected: FixExpressionError) {
+        assert_eq!(
+            Expr::try_from(&Expression::from_str(input).unwrap()),
+            Err(expected)
+        );
+    }
+
+    #[test_case("100 3 + 7 *", 721; "integer result")]
+    #[test_case("1 2 | 3 *", 36; "concatenation")]
+    fn evaluate_matches_postfix(input: &str, expected: isize) {
+        let expr = Expr::try_from(&postfix(input)).unwrap();
+        assert_eq!(expr.evaluate(), Ok(Ratio::from_integer(expected)));
+        assert_eq!(expr.evaluate(), postfix(input).evaluate());
+    }
+
+    #[test]
+    fn evaluate_concatenated_non_tile() {
+        assert_eq!(
+            infix("(1 + 2) | 3").evaluate(),
+            Err(FixExpressionError::ConcatenatedNonTile)
+        );
+    }
+
+    #[test_case("7", 0, 1; "single number")]
+    #[test_case("1 + 2", 1, 3; "single operation")]
+    #[test_case("(1 + 2) * (3 - 4 / 5)", 3, 9; "nested operations")]
+    fn depth_and_size_tests(input: &str, depth: usize, size: usize) {
+        let expr = infix(input);
+        assert_eq!(expr.depth(), depth);
+        assert_eq!(expr.size(), size);
+    }
+
+    struct OperationCounter;
+
+    impl Visitor<usize> for OperationCounter {
+        fn visit_num(&mut self, _: usize) -> usize {
+            0
+        }
+
+        fn visit_bin_op(&mut self, _: Operation, lhs: &Expr, rhs: &Expr) -> usize {
+            1 + lhs.accept(self) + rhs.accept(self)
+        }
+    }
+
+    #[test]
+    fn visitor_counts_operations() {
+        let expr = infix("(1 + 2) * (3 - 4 / 5)");
+        assert_eq!(expr.accept(&mut OperationCounter), 4);
+        assert_eq!(expr.tiles(), vec![1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,272 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::round::{NumbersRound, RoundRules};
+use super::solver::SearchResult;
+
+/// The outcome of solving one line of a batch.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BatchRecord {
+    /// Line number in the input, starting from 1.
+    pub line: usize,
+    /// The round, or `None` if the line could not be read.
+    pub round: Option<NumbersRound>,
+    /// The first solution found, in postfix notation.
+    pub solution: Option<String>,
+    /// The closest integer the search reached, which is the target if it was solved.
+    pub closest: Option<isize>,
+    pub seconds: f64,
+    pub timed_out: bool,
+    pub error: Option<String>,
+}
+
+impl BatchRecord {
+    pub const CSV_HEADER: &'static str =
+        "line,numbers,target,solution,closest,seconds,timed_out,error";
+
+    /// The record as a CSV row matching [`BatchRecord::CSV_HEADER`]. Tiles are separated by
+    /// spaces and missing values are left empty.
+    pub fn to_csv_row(&self) -> String {
+        let (numbers, target) = match &self.round {
+            Some(round) => {
+                let numbers = round
+                    .numbers
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<String>>();
+                (numbers.join(" "), round.target.to_string())
+            }
+            None => (String::new(), String::new()),
+        };
+
+        [
+            self.line.to_string(),
+            numbers,
+            target,
+            csv_field(self.solution.as_deref().unwrap_or_default()),
+            self.closest.map(|c| c.to_string()).unwrap_or_default(),
+            self.seconds.to_string(),
+            self.timed_out.to_string(),
+            csv_field(self.error.as_deref().unwrap_or_default()),
+        ]
+        .join(",")
+    }
+}
+
+/// Quote `field` if it contains characters with a meaning in CSV.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Read a round from one line of a batch, written as `25 50 3 7 2 5 -> 615`, as CSV, or as a
+/// JSON object such as `{"numbers": [25, 50, 3, 7, 2, 5], "target": 615}`.
+pub fn parse_round(line: &str) -> Result<NumbersRound, String> {
+    let line = line.trim();
+    if line.starts_with('{') {
+        #[cfg(feature = "serde")]
+        return serde_json::from_str(line).map_err(|e| e.to_string());
+        #[cfg(not(feature = "serde"))]
+        return Err("reading JSON requires the serde feature".to_string());
+    }
+
+    NumbersRound::from_str(line)
+}
+
+/// Solve the round on each line of `input` with `solve`, using up to `jobs` threads. Blank lines
+/// and lines starting with `#` are skipped, and rounds that break `rules`, if given, are reported
+/// without being solved. Records are returned in input order.
+pub fn solve_batch<F>(
+    input: &str,
+    jobs: usize,
+    rules: Option<&RoundRules>,
+    solve: F,
+) -> Vec<BatchRecord>
+where
+    F: Fn(NumbersRound) -> SearchResult + Sync,
+{
+    let lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .collect::<Vec<(usize, &str)>>();
+
+    let next = AtomicUsize::new(0);
+    let records = Mutex::new(Vec::<BatchRecord>::with_capacity(lines.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, lines.len().max(1)) {
+            scope.spawn(|| {
+                while let Some((i, line)) = lines.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let record = solve_line(i + 1, line, rules, &solve);
+                    records.lock().unwrap().push(record);
+                }
+            });
+        }
+    });
+
+    let mut records = records.into_inner().unwrap();
+    records.sort_by_key(|record| record.line);
+    records
+}
+
+fn solve_line<F>(line: usize, input: &str, rules: Option<&RoundRules>, solve: &F) -> BatchRecord
+where
+    F: Fn(NumbersRound) -> SearchResult,
+{
+    let round = match parse_round(input) {
+        Ok(round) => round,
+        Err(error) => {
+            return BatchRecord {
+                line,
+                round: None,
+                solution: None,
+                closest: None,
+                seconds: 0.0,
+                timed_out: false,
+                error: Some(error),
+            }
+        }
+    };
+
+    if let Some(Err(error)) = rules.map(|rules| rules.validate(&round)) {
+        return BatchRecord {
+            line,
+            round: Some(round),
+            solution: None,
+            closest: None,
+            seconds: 0.0,
+            timed_out: false,
+            error: Some(format!("round breaks the rules: {}", error)),
+        };
+    }
+
+    let now = Instant::now();
+    let result = solve(round.clone());
+    let seconds = now.elapsed().as_secs_f64();
+
+    BatchRecord {
+        line,
+        round: Some(round),
+        solution: result.solutions.first().map(|s| s.to_string()),
+        closest: result
+            .closest
+            .and_then(|c| c.evaluate().ok())
+            .map(|value| value.to_integer()),
+        seconds,
+        timed_out: result.timed_out,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{search, SolverOptions};
+    use test_case::test_case;
+
+    fn solve(round: NumbersRound) -> SearchResult {
+        search(round, &SolverOptions::default(), true)
+    }
+
+    const INPUT: &str = "# broadcast rounds
+25 50 3 7 2 5 -> 615
+
+1,2,831
+not a round
+";
+
+    #[test_case(1; "single thread")]
+    #[test_case(4; "several threads")]
+    fn solve_batch_tests(jobs: usize) {
+        let records = solve_batch(INPUT, jobs, None, solve);
+
+        assert_eq!(
+            records.iter().map(|r| r.line).collect::<Vec<usize>>(),
+            vec![2, 4, 5]
+        );
+        assert!(records[0].solution.is_some());
+        assert_eq!(records[0].closest, Some(615));
+        assert_eq!(records[1].solution, None);
+        assert_eq!(records[1].closest, Some(3));
+        assert_eq!(records[2].round, None);
+        assert_eq!(
+            records[2].error,
+            Some("expected tiles and a target: not a round".to_string())
+        );
+    }
+
+    #[test]
+    fn solve_batch_with_rules() {
+        let records = solve_batch(INPUT, 1, Some(&RoundRules::classic()), solve);
+
+        assert!(records[0].solution.is_some());
+        assert_eq!(records[0].error, None);
+        assert_eq!(records[1].solution, None);
+        assert_eq!(records[1].closest, None);
+        assert_eq!(
+            records[1].error,
+            Some("round breaks the rules: expected 6 tiles but found 2".to_string())
+        );
+    }
+
+    #[test]
+    fn csv_rows() {
+        let records = solve_batch("1,2,831\nbad, round", 1, None, solve);
+
+        assert_eq!(
+            records[0].to_csv_row(),
+            format!("1,1 2,831,,3,{},false,", records[0].seconds)
+        );
+        assert_eq!(records[1].to_csv_row(), "2,,,,,0,false,invalid number: bad");
+        assert_eq!(
+            BatchRecord::CSV_HEADER.split(',').count(),
+            records[0].to_csv_row().split(',').count()
+        );
+    }
+
+    #[test]
+    fn csv_row_quotes_every_text_column() {
+        let record = BatchRecord {
+            line: 1,
+            round: None,
+            solution: Some("\"1, 2\"".to_string()),
+            closest: None,
+            seconds: 0.0,
+            timed_out: false,
+            error: Some("a, b".to_string()),
+        };
+
+        assert_eq!(
+            record.to_csv_row(),
+            "1,,,\"\"\"1, 2\"\"\",,0,false,\"a, b\""
+        );
+    }
+
+    #[test]
+    fn csv_field_quoting() {
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn parse_json_round() {
+        assert_eq!(
+            parse_round(r#"{"numbers": [25, 50, 3, 7, 2, 5], "target": 615}"#),
+            Ok(NumbersRound {
+                numbers: vec![25, 50, 3, 7, 2, 5],
+                target: 615,
+            })
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use rand::Rng;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use vorderman::ast::Expr;
+use vorderman::equivalence::canonical_string;
+use vorderman::expr::PostfixExpression;
+use vorderman::game::{check_working, score};
+use vorderman::round::{NumbersRound, RoundRules};
+use vorderman::solver::{search, SearchResult, SolverOptions};
+
+/// How long the target spins before settling.
+const SPIN: Duration = Duration::from_millis(1500);
+/// Most distinct solutions to list on the reveal screen.
+const MAX_SOLUTIONS: usize = 8;
+/// Longest the solver may run for one round, so that rounds skipped before it finishes don't
+/// leave it running.
+const SOLVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Play numbers rounds in the terminal.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Number of small tiles to deal.
+    #[arg(short, long, default_value_t = 4)]
+    smalls: u8,
+
+    /// Seconds on the clock.
+    #[arg(short, long, default_value_t = 30)]
+    time: u64,
+
+    /// File to keep the high score in. Defaults to `.vorderman_high_score` in the home directory.
+    #[arg(long)]
+    high_score_file: Option<PathBuf>,
+}
+
+enum Screen {
+    Playing,
+    Reveal {
+        /// The player's value and points, or why their working was rejected.
+        result: Result<(isize, u32), String>,
+        /// Distinct solutions, or `None` while the solver is still running.
+        solutions: Option<Vec<PostfixExpression>>,
+        /// Whether the solver gave up before finding every solution.
+        timed_out: bool,
+    },
+}
+
+struct Game {
+    args: Args,
+    options: SolverOptions,
+    round: NumbersRound,
+    started: Instant,
+    input: String,
+    solver: Option<JoinHandle<SearchResult>>,
+    screen: Screen,
+    session_score: u32,
+    high_score: u32,
+    high_score_file: Option<PathBuf>,
+}
+
+impl Game {
+    fn new(args: Args) -> Game {
+        let high_score_file = args.high_score_file.clone().or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".vorderman_high_score"))
+        });
+        let high_score = high_score_file
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| contents.trim().parse().ok())
+            .unwrap_or(0);
+
+        let round = NumbersRound::new(args.smalls).unwrap();
+        let options = SolverOptions::default();
+        Game {
+            solver: Some(solve_in_background(&round, &options)),
+            round,
+            args,
+            options,
+            started: Instant::now(),
+            input: String::new(),
+            screen: Screen::Playing,
+            session_score: 0,
+            high_score,
+            high_score_file,
+        }
+    }
+
+    /// Start a new round, solving it in the background while the player thinks.
+    fn deal(&mut self) {
+        self.round = NumbersRound::new(self.args.smalls).unwrap();
+        self.started = Instant::now();
+        self.input.clear();
+        self.screen = Screen::Playing;
+        // A solver still running for the skipped round stops by itself at its timeout
+        self.solver = Some(solve_in_background(&self.round, &self.options));
+    }
+
+    fn time_limit(&self) -> Duration {
+        Duration::from_secs(self.args.time)
+    }
+
+    /// The value of the working typed so far and the points it would score.
+    fn check_input(&self) -> Result<(isize, u32), String> {
+        let value =
+            check_working(&self.round, &self.input, &self.options).map_err(|e| e.to_string())?;
+        if !value.is_integer() {
+            return Err(format!("{} is not a whole number", value));
+        }
+        let value = value.to_integer();
+        Ok((value, score(self.round.target, value)))
+    }
+
+    fn reveal(&mut self) {
+        let result = self.check_input();
+        if let Ok((_, points)) = result {
+            self.session_score += points;
+        }
+        if self.session_score > self.high_score {
+            self.high_score = self.session_score;
+            if let Some(path) = &self.high_score_file {
+                // Losing the high score is not worth interrupting the game for
+                let _ = fs::write(path, self.high_score.to_string());
+            }
+        }
+
+        self.screen = Screen::Reveal {
+            result,
+            solutions: None,
+            timed_out: false,
+        };
+    }
+
+    /// Collect the solver's results once it has finished, without blocking the UI.
+    fn collect_solutions(&mut self) {
+        let Screen::Reveal {
+            solutions,
+            timed_out,
+            ..
+        } = &mut self.screen
+        else {
+            return;
+        };
+        if solutions.is_some() || !self.solver.as_ref().is_some_and(|s| s.is_finished()) {
+            return;
+        }
+
+        let result = self.solver.take().unwrap().join().unwrap();
+        let mut seen = HashSet::<String>::new();
+        *timed_out = result.timed_out;
+        *solutions = Some(
+            result
+                .solutions
+                .into_iter()
+                .filter(|s| seen.insert(canonical_string(s).unwrap()))
+                .collect(),
+        );
+    }
+
+    /// Handle a key press, returning `false` when the player quits.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match (&self.screen, code) {
+            (_, KeyCode::Esc) => return false,
+            (Screen::Playing, KeyCode::Enter) => self.reveal(),
+            (Screen::Playing, KeyCode::Backspace) => {
+                self.input.pop();
+            }
+            (Screen::Playing, KeyCode::Char(c)) => self.input.push(c),
+            (Screen::Reveal { .. }, KeyCode::Char('q')) => return false,
+            (Screen::Reveal { .. }, KeyCode::Enter | KeyCode::Char('n')) => self.deal(),
+            _ => {}
+        }
+        true
+    }
+
+    fn tick(&mut self) {
+        if matches!(self.screen, Screen::Playing) && self.started.elapsed() >= self.time_limit() {
+            self.reveal();
+        }
+        self.collect_solutions();
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [target, tiles, clock, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_target(frame, target);
+        self.draw_tiles(frame, tiles);
+        self.draw_clock(frame, clock);
+        match &self.screen {
+            Screen::Playing => self.draw_input(frame, body),
+            Screen::Reveal {
+                result,
+                solutions,
+                timed_out,
+            } => self.draw_reveal(frame, body, result, solutions.as_deref(), *timed_out),
+        }
+
+        let keys = match self.screen {
+            Screen::Playing => "Enter: declare  Esc: quit",
+            Screen::Reveal { .. } => "Enter: next round  q: quit",
+        };
+        frame.render_widget(
+            Line::from(format!(
+                "Score: {}  High score: {}  {}",
+                self.session_score, self.high_score, keys
+            ))
+            .dark_gray(),
+            footer,
+        );
+    }
+
+    fn draw_target(&self, frame: &mut Frame, area: Rect) {
+        let target = if self.started.elapsed() < SPIN {
+            rand::thread_rng().gen_range(100..=999)
+        } else {
+            self.round.target
+        };
+        frame.render_widget(
+            Paragraph::new(target.to_string().bold().yellow())
+                .centered()
+                .block(Block::bordered().title("Target")),
+            area,
+        );
+    }
+
+    fn draw_tiles(&self, frame: &mut Frame, area: Rect) {
+        let constraints = vec![Constraint::Fill(1); self.round.numbers.len()];
+        let areas = Layout::horizontal(constraints).split(area);
+        for (tile, area) in self.round.numbers.iter().zip(areas.iter()) {
+            frame.render_widget(
+                Paragraph::new(tile.to_string().bold())
+                    .centered()
+                    .block(Block::bordered()),
+                *area,
+            );
+        }
+    }
+
+    fn draw_clock(&self, frame: &mut Frame, area: Rect) {
+        let limit = self.time_limit();
+        let remaining = match self.screen {
+            Screen::Playing => limit.saturating_sub(self.started.elapsed()),
+            Screen::Reveal { .. } => Duration::ZERO,
+        };
+        let ratio = if limit.is_zero() {
+            0.0
+        } else {
+            remaining.as_secs_f64() / limit.as_secs_f64()
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title("Clock"))
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio(ratio)
+                .label(format!("{:.0}s", remaining.as_secs_f64().ceil())),
+            area,
+        );
+    }
+
+    fn draw_input(&self, frame: &mut Frame, area: Rect) {
+        let status = if self.input.trim().is_empty() {
+            Span::from("Type your working, e.g. (100 + 3) * 7").dark_gray()
+        } else {
+            match self.check_input() {
+                Ok((value, points)) => {
+                    Span::from(format!("= {} ({} points)", value, points)).green()
+                }
+                Err(e) => Span::from(e).red(),
+            }
+        };
+
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(format!("{}_", self.input)),
+                Line::from(status),
+            ])
+            .block(Block::bordered().title("Working")),
+            area,
+        );
+    }
+
+    fn draw_reveal(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        result: &Result<(isize, u32), String>,
+        solutions: Option<&[PostfixExpression]>,
+        timed_out: bool,
+    ) {
+        let mut lines = vec![match result {
+            Ok((value, points)) => {
+                Line::from(format!("You declared {} for {} points.", value, points)).green()
+            }
+            Err(e) => Line::from(format!("No points: {}", e)).red(),
+        }];
+        lines.push(Line::from(""));
+
+        match solutions {
+            None => lines.push(Line::from("Solving...").dark_gray()),
+            Some([]) if timed_out => lines.push(Line::from("No solution found in time.")),
+            Some([]) => lines.push(Line::from("The target can't be reached.")),
+            Some(solutions) => {
+                lines.push(Line::from(format!(
+                    "{}{} distinct solutions, for example:",
+                    if timed_out { "At least " } else { "" },
+                    solutions.len()
+                )));
+                for solution in solutions.iter().take(MAX_SOLUTIONS) {
+                    lines.push(Line::from(format!(
+                        "  {}",
+                        Expr::try_from(solution).unwrap()
+                    )));
+                }
+            }
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("Reveal")),
+            area,
+        );
+    }
+}
+
+fn solve_in_background(round: &NumbersRound, options: &SolverOptions) -> JoinHandle<SearchResult> {
+    let round = round.clone();
+    let options = SolverOptions {
+        timeout: Some(SOLVER_TIMEOUT),
+        ..options.clone()
+    };
+    thread::spawn(move || search(round, &options, false))
+}
+
+fn run(terminal: &mut DefaultTerminal, mut game: Game) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| game.draw(frame))?;
+
+        if event::poll(Duration::from_millis(50))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !game.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+        game.tick();
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    // Rounds are dealt with `unwrap`, so reject counts that can't make one up front
+    if let Err(e) = RoundRules::classic().bigs_for(usize::from(args.smalls)) {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!("invalid value '{}' for '--smalls': {}", args.smalls, e),
+            )
+            .exit();
+    }
+    let game = Game::new(args);
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, game);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,269 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::round::{NumbersRound, RoundRules};
+
+/// The tiles of a numbers round, kept face down in two groups as on the show: the large tiles
+/// on the top row and the small tiles below. Tiles dealt into a round stay out of the deck until
+/// it is reshuffled, so consecutive rounds can be dealt from the same deck.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileDeck {
+    smalls: Vec<usize>,
+    bigs: Vec<usize>,
+    dealt_smalls: Vec<usize>,
+    dealt_bigs: Vec<usize>,
+    targets: RangeInclusive<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeckError {
+    NotEnoughSmalls {
+        requested: usize,
+        remaining: usize,
+    },
+    NotEnoughBigs {
+        requested: usize,
+        remaining: usize,
+    },
+    /// The deck's range of targets is empty.
+    NoTargets,
+}
+
+impl fmt::Display for DeckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeckError::NotEnoughSmalls {
+                requested,
+                remaining,
+            } => write!(
+                f,
+                "requested {} small tiles but only {} remain",
+                requested, remaining
+            ),
+            DeckError::NotEnoughBigs {
+                requested,
+                remaining,
+            } => write!(
+                f,
+                "requested {} large tiles but only {} remain",
+                requested, remaining
+            ),
+            DeckError::NoTargets => write!(f, "there are no targets to choose from"),
+        }
+    }
+}
+
+impl std::error::Error for DeckError {}
+
+impl Default for TileDeck {
+    fn default() -> Self {
+        TileDeck::new()
+    }
+}
+
+impl TileDeck {
+    /// A shuffled deck of the show's tiles: two each of 1 to 10, and 25, 50, 75 and 100.
+    pub fn new() -> TileDeck {
+        TileDeck::from_rules(&RoundRules::classic())
+    }
+
+    /// A shuffled deck of the tiles in `rules`, dealing rounds with targets in its range.
+    pub fn from_rules(rules: &RoundRules) -> TileDeck {
+        let mut deck = TileDeck::from_tiles(
+            rules.smalls.clone(),
+            rules.bigs.clone(),
+            rules.targets.clone(),
+        );
+        deck.shuffle();
+        deck
+    }
+
+    /// A deck holding exactly these tiles, which are dealt in the order given, dealing rounds
+    /// with targets in `targets`.
+    pub fn from_tiles(
+        smalls: Vec<usize>,
+        bigs: Vec<usize>,
+        targets: RangeInclusive<usize>,
+    ) -> TileDeck {
+        TileDeck {
+            smalls,
+            bigs,
+            dealt_smalls: Vec::new(),
+            dealt_bigs: Vec::new(),
+            targets,
+        }
+    }
+
+    /// Small tiles left in the deck, in the order they will be dealt.
+    pub fn smalls(&self) -> &[usize] {
+        &self.smalls
+    }
+
+    /// Large tiles left in the deck, in the order they will be dealt.
+    pub fn bigs(&self) -> &[usize] {
+        &self.bigs
+    }
+
+    /// Small tiles dealt since the deck was last reshuffled.
+    pub fn dealt_smalls(&self) -> &[usize] {
+        &self.dealt_smalls
+    }
+
+    /// Large tiles dealt since the deck was last reshuffled.
+    pub fn dealt_bigs(&self) -> &[usize] {
+        &self.dealt_bigs
+    }
+
+    /// Shuffle the tiles left in the deck.
+    pub fn shuffle(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.smalls.shuffle(&mut rng);
+        self.bigs.shuffle(&mut rng);
+    }
+
+    /// Return every dealt tile to the deck and shuffle it.
+    pub fn reshuffle(&mut self) {
+        self.smalls.append(&mut self.dealt_smalls);
+        self.bigs.append(&mut self.dealt_bigs);
+        self.shuffle();
+    }
+
+    pub fn draw_small(&mut self) -> Option<usize> {
+        self.deal(0, 1).ok().map(|tiles| tiles[0])
+    }
+
+    pub fn draw_big(&mut self) -> Option<usize> {
+        self.deal(1, 0).ok().map(|tiles| tiles[0])
+    }
+
+    /// Deal the contestant's choice of `n_big` tiles from the top and `n_small` from the bottom,
+    /// large tiles first. Nothing is dealt if the deck cannot supply both.
+    pub fn deal(&mut self, n_big: usize, n_small: usize) -> Result<Vec<usize>, DeckError> {
+        if n_big > self.bigs.len() {
+            return Err(DeckError::NotEnoughBigs {
+                requested: n_big,
+                remaining: self.bigs.len(),
+            });
+        }
+        if n_small > self.smalls.len() {
+            return Err(DeckError::NotEnoughSmalls {
+                requested: n_small,
+                remaining: self.smalls.len(),
+            });
+        }
+
+        let bigs = self.bigs.drain(..n_big).collect::<Vec<usize>>();
+        let smalls = self.smalls.drain(..n_small).collect::<Vec<usize>>();
+        self.dealt_bigs.extend(&bigs);
+        self.dealt_smalls.extend(&smalls);
+        Ok([bigs, smalls].concat())
+    }
+
+    /// Deal a round of `n_big` large and `n_small` small tiles with a random target.
+    pub fn deal_round(&mut self, n_big: usize, n_small: usize) -> Result<NumbersRound, DeckError> {
+        if self.targets.is_empty() {
+            return Err(DeckError::NoTargets);
+        }
+        let numbers = self.deal(n_big, n_small)?;
+        let target = rand::thread_rng().gen_range(self.targets.clone());
+        Ok(NumbersRound { numbers, target })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn deck() -> TileDeck {
+        TileDeck::from_tiles(vec![1, 2, 3, 4, 5, 6, 7], vec![25, 50, 75], 100..=999)
+    }
+
+    #[test]
+    fn new_deck() {
+        let deck = TileDeck::new();
+        let mut smalls = deck.smalls().to_vec();
+        smalls.sort();
+        let mut bigs = deck.bigs().to_vec();
+        bigs.sort();
+
+        assert_eq!(
+            smalls,
+            (1..=10).flat_map(|n| [n, n]).collect::<Vec<usize>>()
+        );
+        assert_eq!(bigs, vec![25, 50, 75, 100]);
+        assert!(deck.dealt_smalls().is_empty());
+        assert!(deck.dealt_bigs().is_empty());
+    }
+
+    #[test]
+    fn deal_without_replacement() {
+        let mut deck = deck();
+
+        assert_eq!(deck.deal(1, 3), Ok(vec![25, 1, 2, 3]));
+        assert_eq!(deck.deal(2, 2), Ok(vec![50, 75, 4, 5]));
+        assert_eq!(deck.smalls(), &[6, 7]);
+        assert!(deck.bigs().is_empty());
+        assert_eq!(deck.dealt_smalls(), &[1, 2, 3, 4, 5]);
+        assert_eq!(deck.dealt_bigs(), &[25, 50, 75]);
+        assert_eq!(deck.draw_big(), None);
+        assert_eq!(deck.draw_small(), Some(6));
+    }
+
+    #[test_case(4, 0, DeckError::NotEnoughBigs { requested: 4, remaining: 3 }; "bigs")]
+    #[test_case(0, 8, DeckError::NotEnoughSmalls { requested: 8, remaining: 7 }; "smalls")]
+    fn deal_errors(n_big: usize, n_small: usize, expected: DeckError) {
+        let mut deck = deck();
+
+        assert_eq!(deck.deal(n_big, n_small), Err(expected));
+        assert_eq!(deck, self::deck());
+    }
+
+    #[test]
+    fn reshuffle() {
+        let mut deck = deck();
+        let round = deck.deal_round(2, 4).unwrap();
+        assert_eq!(round.numbers, vec![25, 50, 1, 2, 3, 4]);
+        assert!((100..=999).contains(&round.target));
+
+        deck.reshuffle();
+        let mut smalls = deck.smalls().to_vec();
+        smalls.sort();
+        let mut bigs = deck.bigs().to_vec();
+        bigs.sort();
+
+        assert_eq!(smalls, vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(bigs, vec![25, 50, 75]);
+        assert!(deck.dealt_smalls().is_empty());
+        assert!(deck.dealt_bigs().is_empty());
+    }
+
+    #[test]
+    fn deck_from_rules() {
+        let rules = RoundRules {
+            smalls: vec![1, 1, 2],
+            bigs: vec![10],
+            tiles: 2,
+            targets: 7..=7,
+            allowed_bigs: 0..=1,
+        };
+        let mut deck = TileDeck::from_rules(&rules);
+        let round = deck.deal_round(1, 1).unwrap();
+
+        assert_eq!(round.numbers[0], 10);
+        assert_eq!(round.target, 7);
+        assert_eq!(deck.smalls().len(), 2);
+    }
+
+    #[test]
+    fn deal_round_without_targets() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let mut deck = TileDeck::from_tiles(vec![1, 2], vec![25], 10..=1);
+
+        assert_eq!(deck.deal_round(1, 1), Err(DeckError::NoTargets));
+        assert_eq!(deck.smalls(), &[1, 2]);
+    }
+}
@@ -0,0 +1,174 @@
+use super::expr::{Expression, FixExpressionError, LexError, Span};
+use super::parse::ParseError;
+
+/// An error message tied to the region of the user's input that caused it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn from_lex_error(error: &LexError, source: &str) -> Diagnostic {
+        let span = match error {
+            LexError::InvalidCharacterAtIndex(i, c) => Span {
+                start: *i,
+                end: i + c.len_utf8(),
+            },
+            LexError::NumberTooLargeAtIndex(start, end) => Span {
+                start: *start,
+                end: *end,
+            },
+            LexError::InvalidCharacter(_) => whole(source),
+        };
+
+        Diagnostic {
+            message: error.to_string(),
+            span,
+        }
+    }
+
+    /// Build a diagnostic for an error raised on `expression`, which was lexed from `source`.
+    pub fn from_expression_error(
+        error: &FixExpressionError,
+        expression: &Expression,
+        source: &str,
+    ) -> Diagnostic {
+        let tokens = &expression.0;
+        let span = match error {
+            // An index past the last token means the input ended before the token was found
+            FixExpressionError::StackUnderflow(i)
+            | FixExpressionError::UnexpectedParenthesis(i)
+            | FixExpressionError::UnmatchedParenthesis(i)
+            | FixExpressionError::UnexpectedToken(i) => tokens
+                .get(*i)
+                .and_then(|token| token.span)
+                .unwrap_or_else(|| end(source)),
+            FixExpressionError::UnexpectedEnd => end(source),
+            _ => match (
+                tokens.first().and_then(|t| t.span),
+                tokens.last().and_then(|t| t.span),
+            ) {
+                (Some(first), Some(last)) => Span {
+                    start: first.start,
+                    end: last.end,
+                },
+                _ => whole(source),
+            },
+        };
+
+        // Token indices are meaningless to a player, so describe the token itself instead
+        let message = match error {
+            FixExpressionError::StackUnderflow(_) => "operation is missing an operand".to_string(),
+            FixExpressionError::UnexpectedParenthesis(_) => "unexpected parenthesis".to_string(),
+            FixExpressionError::UnmatchedParenthesis(_) => "unmatched parenthesis".to_string(),
+            FixExpressionError::UnexpectedToken(_) => "unexpected token".to_string(),
+            e => e.to_string(),
+        };
+
+        Diagnostic { message, span }
+    }
+
+    pub fn from_parse_error(error: &ParseError, source: &str) -> Diagnostic {
+        match error {
+            ParseError::Lex(e) => Diagnostic::from_lex_error(e, source),
+            ParseError::Expression(e) => match Expression::lex(source) {
+                Ok(expression) => Diagnostic::from_expression_error(e, &expression, source),
+                Err(e) => Diagnostic::from_lex_error(&e, source),
+            },
+            e => Diagnostic {
+                message: e.to_string(),
+                span: whole(source),
+            },
+        }
+    }
+
+    /// Print `source` with the offending region underlined and the message alongside, e.g.
+    ///
+    /// ```text
+    /// 1 + + 2
+    ///     ^ unexpected token
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let column = source[..self.span.start].chars().count();
+        let width = source[self.span.start..self.span.end]
+            .chars()
+            .count()
+            .max(1);
+
+        format!(
+            "{}\n{}{} {}",
+            source.trim_end(),
+            " ".repeat(column),
+            "^".repeat(width),
+            self.message
+        )
+    }
+}
+
+fn end(source: &str) -> Span {
+    let end = source.trim_end().len();
+    Span { start: end, end }
+}
+
+fn whole(source: &str) -> Span {
+    let start = source.len() - source.trim_start().len();
+    Span {
+        start,
+        end: source.trim_end().len().max(start),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::{parse, parse_any_notation, Notation};
+    use test_case::test_case;
+
+    #[test_case("  1 + a", "  1 + a\n      ^ invalid character 'a' at index 6"; "invalid character after leading spaces")]
+    #[test_case("1 + 123456789012345678901 * 2", "1 + 123456789012345678901 * 2\n    ^^^^^^^^^^^^^^^^^^^^^ number at index 4 is too large"; "number too large")]
+    #[test_case("123456789012345678901x2", "123456789012345678901x2\n^^^^^^^^^^^^^^^^^^^^^ number at index 0 is too large"; "number too large before multiplication")]
+    #[test_case("1 × a", "1 × a\n    ^ invalid character 'a' at index 5"; "multi-byte characters before error")]
+    #[test_case("1 + é", "1 + é\n    ^ invalid character 'é' at index 4"; "multi-byte invalid character")]
+    fn lex_error_tests(input: &str, expected: &str) {
+        let error = parse(input, Notation::Infix).unwrap_err();
+        assert_eq!(
+            Diagnostic::from_parse_error(&error, input).render(input),
+            expected
+        );
+    }
+
+    #[test_case("1 + + 2", Notation::Infix, "1 + + 2\n    ^ unexpected token"; "unexpected token")]
+    #[test_case(" (1 + 2 ", Notation::Infix, " (1 + 2\n ^ unmatched parenthesis"; "unmatched parenthesis")]
+    #[test_case("3 * (4 - ", Notation::Infix, "3 * (4 -\n        ^ expression ends unexpectedly"; "unexpected end")]
+    #[test_case("1 2 + * 3", Notation::Postfix, "1 2 + * 3\n      ^ operation is missing an operand"; "stack underflow")]
+    #[test_case(" 1 2 3 +", Notation::Postfix, " 1 2 3 +\n ^^^^^^^ 2 values are left over without an operation"; "leftover operands")]
+    #[test_case("1 (2) +", Notation::Postfix, "1 (2) +\n  ^ unexpected parenthesis"; "unexpected parenthesis")]
+    fn expression_error_tests(input: &str, notation: Notation, expected: &str) {
+        let error = parse(input, notation).unwrap_err();
+        assert_eq!(
+            Diagnostic::from_parse_error(&error, input).render(input),
+            expected
+        );
+    }
+
+    #[test]
+    fn unrecognised_notation() {
+        let input = " 1 + + 2";
+        let error = parse_any_notation(input).unwrap_err();
+        let diagnostic = Diagnostic::from_parse_error(&error, input);
+        assert_eq!(diagnostic.span, Span { start: 1, end: 8 });
+    }
+
+    #[test]
+    fn token_index_past_end() {
+        let input = "1 2 ";
+        let expression = Expression::lex(input).unwrap();
+        let diagnostic = Diagnostic::from_expression_error(
+            &FixExpressionError::UnexpectedToken(2),
+            &expression,
+            input,
+        );
+        assert_eq!(diagnostic.render(input), "1 2\n   ^ unexpected token");
+    }
+}
@@ -0,0 +1,144 @@
+use super::ast::Expr;
+use super::expr::{FixExpressionError, Operation, PostfixExpression};
+
+/// A normalised string for `expr` that is identical for any two expressions that differ only by
+/// commutativity, associativity, or redistributing subtractions and divisions, e.g. `a - (b - c)`
+/// and `(a + c) - b`. Suitable for use as a hash key when grouping solutions.
+pub fn canonical_string(expr: &PostfixExpression) -> Result<String, FixExpressionError> {
+    Ok(canonical(&Expr::try_from(expr)?))
+}
+
+/// Whether two expressions are the same solution up to rearrangement, as per [`canonical_string`].
+pub fn are_equivalent(
+    first: &PostfixExpression,
+    second: &PostfixExpression,
+) -> Result<bool, FixExpressionError> {
+    Ok(canonical_string(first)? == canonical_string(second)?)
+}
+
+fn canonical(expr: &Expr) -> String {
+    match expr {
+        Expr::Num(n) => n.to_string(),
+        Expr::BinOp { op, lhs, rhs } => match op {
+            Operation::Add | Operation::Subtract => {
+                group(expr, Operation::Add, Operation::Subtract)
+            }
+            Operation::Multiply | Operation::Divide => {
+                group(expr, Operation::Multiply, Operation::Divide)
+            }
+            _ => format!("({} {} {})", canonical(lhs), op, canonical(rhs)),
+        },
+    }
+}
+
+/// Flatten a chain of `positive` and `inverse` operations, such as `+` and `-`, into sorted
+/// lists of terms that are combined with each.
+fn group(expr: &Expr, positive: Operation, inverse: Operation) -> String {
+    let mut terms = Terms {
+        positive,
+        inverse,
+        positive_terms: Vec::<String>::new(),
+        inverse_terms: Vec::<String>::new(),
+    };
+    terms.collect(expr, false);
+    terms.positive_terms.sort();
+    terms.inverse_terms.sort();
+
+    let mut result = terms.positive_terms.join(&format!(" {} ", positive));
+    for term in terms.inverse_terms {
+        result.push_str(&format!(" {} {}", inverse, term));
+    }
+    format!("({})", result)
+}
+
+struct Terms {
+    positive: Operation,
+    inverse: Operation,
+    positive_terms: Vec<String>,
+    inverse_terms: Vec<String>,
+}
+
+impl Terms {
+    fn collect(&mut self, expr: &Expr, inverted: bool) {
+        match expr {
+            Expr::BinOp { op, lhs, rhs } if *op == self.positive || *op == self.inverse => {
+                self.collect(lhs, inverted);
+                self.collect(rhs, inverted ^ (*op == self.inverse));
+            }
+            _ if inverted => self.inverse_terms.push(canonical(expr)),
+            _ => self.positive_terms.push(canonical(expr)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::Expression;
+    use crate::round::NumbersRound;
+    use crate::solver::find_solutions;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn postfix(input: &str) -> PostfixExpression {
+        PostfixExpression(Expression::from_str(input).unwrap())
+    }
+
+    #[test_case("1 2 +", "2 1 +"; "commutative addition")]
+    #[test_case("1 2 + 3 +", "1 2 3 + +"; "associative addition")]
+    #[test_case("3 1 + 2 +", "2 3 1 + +"; "commutative and associative addition")]
+    #[test_case("10 4 3 - -", "10 3 + 4 -"; "subtraction of a difference")]
+    #[test_case("10 4 - 3 -", "10 4 3 + -"; "subtraction of a sum")]
+    #[test_case("8 4 2 / /", "8 2 * 4 /"; "division by a quotient")]
+    #[test_case("25 4 * 3 7 + *", "7 3 + 4 25 * *"; "nested products and sums")]
+    #[test_case("2 3 ^ 1 +", "1 2 3 ^ +"; "power is an opaque term")]
+    fn equivalent_expressions(first: &str, second: &str) {
+        assert_eq!(are_equivalent(&postfix(first), &postfix(second)), Ok(true));
+        assert_eq!(
+            canonical_string(&postfix(first)),
+            canonical_string(&postfix(second))
+        );
+    }
+
+    #[test_case("1 2 -", "2 1 -"; "subtraction is not commutative")]
+    #[test_case("2 3 ^", "3 2 ^"; "power is not commutative")]
+    #[test_case("10 2 3 * +", "10 2 + 3 *"; "different structure")]
+    #[test_case("7 3 %", "3 7 %"; "modulo is not commutative")]
+    fn inequivalent_expressions(first: &str, second: &str) {
+        assert_eq!(are_equivalent(&postfix(first), &postfix(second)), Ok(false));
+    }
+
+    #[test_case("1 2 +", "(1 + 2)")]
+    #[test_case("10 4 3 - -", "(10 + 3 - 4)")]
+    #[test_case("100 3 + 7 * 2 /", "((100 + 3) * 7 / 2)")]
+    fn canonical_string_tests(input: &str, expected: &str) {
+        assert_eq!(canonical_string(&postfix(input)), Ok(expected.to_string()));
+    }
+
+    #[test]
+    fn invalid_expression() {
+        assert_eq!(
+            are_equivalent(&postfix("1 +"), &postfix("1")),
+            Err(FixExpressionError::StackUnderflow(1))
+        );
+    }
+
+    #[test]
+    fn solver_finds_equivalent_solutions() {
+        let solutions = find_solutions(
+            NumbersRound {
+                numbers: vec![8, 3, 7, 2, 5, 4],
+                target: 120,
+            },
+            false,
+        );
+        let distinct = solutions
+            .iter()
+            .map(|s| canonical_string(s).unwrap())
+            .collect::<HashSet<String>>();
+
+        assert!(!distinct.is_empty());
+        assert!(distinct.len() < solutions.len());
+    }
+}
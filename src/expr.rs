@@ -3,6 +3,9 @@ use std::str::FromStr;
 use std::vec::Vec;
 
 use num::rational::Ratio;
+use num::traits::{checked_pow, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Signed, Zero};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 const BASE: u32 = 10;
 
@@ -10,26 +13,261 @@ const BASE: u32 = 10;
 pub enum LexError {
     InvalidCharacter(char),
     InvalidCharacterAtIndex(usize, char),
+    /// The number from the first index up to the second does not fit in a `usize`.
+    NumberTooLargeAtIndex(usize, usize),
 }
 
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::InvalidCharacter(c) => write!(f, "invalid character '{}'", c),
+            LexError::InvalidCharacterAtIndex(i, c) => {
+                write!(f, "invalid character '{}' at index {}", c, i)
+            }
+            LexError::NumberTooLargeAtIndex(i, _) => {
+                write!(f, "number at index {} is too large", i)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LexError {}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Operation {
     Add,
     Subtract,
     Multiply,
     Divide,
+    Power,
+    Modulo,
+    /// Join the digits of two tiles, e.g. `1 | 2` is `12`. Only valid between raw tiles.
+    Concatenate,
 }
 
-impl fmt::Display for Operation {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let c = match self {
-            Operation::Add => '+',
-            Operation::Subtract => '-',
-            Operation::Multiply => '*',
-            Operation::Divide => '/',
+impl Operation {
+    /// The four operations permitted on the television show.
+    pub const STANDARD: [Operation; 4] = [
+        Operation::Add,
+        Operation::Subtract,
+        Operation::Multiply,
+        Operation::Divide,
+    ];
+
+    /// Every operation understood by the lexer and evaluator.
+    pub const ALL: [Operation; 7] = [
+        Operation::Add,
+        Operation::Subtract,
+        Operation::Multiply,
+        Operation::Divide,
+        Operation::Power,
+        Operation::Modulo,
+        Operation::Concatenate,
+    ];
+
+    /// Apply the operation to `lhs` and `rhs`, checking for overflow and undefined results
+    /// rather than panicking.
+    pub fn checked_apply(
+        &self,
+        lhs: &Ratio<isize>,
+        rhs: &Ratio<isize>,
+    ) -> Result<Ratio<isize>, FixExpressionError> {
+        let result = match self {
+            Operation::Add => lhs.checked_add(rhs),
+            Operation::Subtract => lhs.checked_sub(rhs),
+            Operation::Multiply => lhs.checked_mul(rhs),
+            Operation::Divide => {
+                if rhs.is_zero() {
+                    return Err(FixExpressionError::DivisionByZero);
+                }
+                lhs.checked_div(rhs)
+            }
+            Operation::Power => {
+                if !rhs.is_integer() {
+                    return Err(FixExpressionError::NonIntegerExponent);
+                }
+                if rhs.is_negative() && lhs.is_zero() {
+                    return Err(FixExpressionError::DivisionByZero);
+                }
+                let magnitude = checked_pow(*lhs, rhs.numer().unsigned_abs());
+                if rhs.is_negative() {
+                    magnitude.and_then(|m| Ratio::one().checked_div(&m))
+                } else {
+                    magnitude
+                }
+            }
+            Operation::Modulo => {
+                if rhs.is_zero() {
+                    return Err(FixExpressionError::DivisionByZero);
+                }
+                // Truncated remainder, matching `Ratio`'s `%` operator
+                lhs.checked_div(rhs)
+                    .and_then(|quotient| quotient.trunc().checked_mul(rhs))
+                    .and_then(|multiple| lhs.checked_sub(&multiple))
+            }
+            Operation::Concatenate => {
+                if !lhs.is_integer() || !rhs.is_integer() || lhs.is_negative() || rhs.is_negative()
+                {
+                    return Err(FixExpressionError::ConcatenatedNonTile);
+                }
+                concatenate(lhs.numer().unsigned_abs(), rhs.numer().unsigned_abs())
+                    .and_then(|n| isize::try_from(n).ok())
+                    .map(Ratio::from_integer)
+            }
         };
 
-        write!(f, "{}", c)
+        result.ok_or(FixExpressionError::Overflow)
+    }
+}
+
+/// Join the decimal digits of `first` and `second`, returning `None` on overflow. Tiles are
+/// decimal numbers, so this does not depend on the radix an expression is written in.
+pub(crate) fn concatenate(first: usize, second: usize) -> Option<usize> {
+    // Zero still has one digit
+    let mut shift = BASE as usize;
+    let mut remaining_digits = second / BASE as usize;
+    while remaining_digits > 0 {
+        shift = shift.checked_mul(BASE as usize)?;
+        remaining_digits /= BASE as usize;
+    }
+    first.checked_mul(shift)?.checked_add(second)
+}
+
+/// The base that number literals are written in.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    #[default]
+    Decimal,
+    Hexadecimal,
+}
+
+impl Radix {
+    pub fn value(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hexadecimal => 16,
+        }
+    }
+
+    /// The prefix that marks a literal in this radix, e.g. `0x`. Decimal has none.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Radix::Binary => "0b",
+            Radix::Octal => "0o",
+            Radix::Decimal => "",
+            Radix::Hexadecimal => "0x",
+        }
+    }
+
+    /// Write `n` with this radix's prefix, so that it lexes back to `n` whatever the default
+    /// radix of the lexer.
+    pub fn format(&self, n: usize) -> String {
+        match self {
+            Radix::Binary => format!("0b{:b}", n),
+            Radix::Octal => format!("0o{:o}", n),
+            Radix::Decimal => n.to_string(),
+            Radix::Hexadecimal => format!("0x{:X}", n),
+        }
+    }
+
+    /// The radix of a prefixed literal at the start of `s`, which must be followed by at least
+    /// one digit in that radix. A prefix letter that is a digit in `current` is read as a digit,
+    /// so in hexadecimal `0b1` is `0xB1` rather than binary.
+    fn from_prefix(s: &str, current: Radix) -> Option<Radix> {
+        let mut chars = s.chars();
+        let radix = match (chars.next(), chars.next()) {
+            (Some('0'), Some(c)) if c.is_digit(current.value()) => return None,
+            (Some('0'), Some('b' | 'B')) => Radix::Binary,
+            (Some('0'), Some('o' | 'O')) => Radix::Octal,
+            (Some('0'), Some('x' | 'X')) => Radix::Hexadecimal,
+            _ => return None,
+        };
+
+        chars
+            .next()
+            .filter(|c| c.is_digit(radix.value()))
+            .map(|_| radix)
+    }
+}
+
+impl TryFrom<u32> for Radix {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Radix, Self::Error> {
+        match value {
+            2 => Ok(Radix::Binary),
+            8 => Ok(Radix::Octal),
+            10 => Ok(Radix::Decimal),
+            16 => Ok(Radix::Hexadecimal),
+            _ => Err(format!("unsupported radix: {}", value)),
+        }
+    }
+}
+
+impl FromStr for Radix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "binary" | "bin" => Ok(Radix::Binary),
+            "octal" | "oct" => Ok(Radix::Octal),
+            "decimal" | "dec" => Ok(Radix::Decimal),
+            "hexadecimal" | "hex" => Ok(Radix::Hexadecimal),
+            value => value
+                .parse::<u32>()
+                .map_err(|_| format!("unknown radix: {}", s))
+                .and_then(Radix::try_from),
+        }
+    }
+}
+
+/// The character set used when rendering operations.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Symbols {
+    /// Keyboard-friendly symbols such as `*` and `/`, which the lexer reads back unchanged.
+    #[default]
+    Ascii,
+    /// Typeset symbols such as `×`, `÷` and `−`, which the lexer also accepts.
+    Typographic,
+}
+
+/// How to render an expression: which operation symbols to use and which radix to write
+/// numbers in.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub symbols: Symbols,
+    pub radix: Radix,
+}
+
+impl Operation {
+    pub fn symbol(&self, symbols: Symbols) -> char {
+        match (self, symbols) {
+            (Operation::Add, _) => '+',
+            (Operation::Subtract, Symbols::Ascii) => '-',
+            (Operation::Subtract, Symbols::Typographic) => '−',
+            (Operation::Multiply, Symbols::Ascii) => '*',
+            (Operation::Multiply, Symbols::Typographic) => '×',
+            (Operation::Divide, Symbols::Ascii) => '/',
+            (Operation::Divide, Symbols::Typographic) => '÷',
+            (Operation::Power, _) => '^',
+            (Operation::Modulo, _) => '%',
+            (Operation::Concatenate, _) => '|',
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol(Symbols::Ascii))
     }
 }
 
@@ -39,15 +277,24 @@ impl TryFrom<char> for Operation {
     fn try_from(input: char) -> Result<Operation, Self::Error> {
         match input {
             '+' => Ok(Operation::Add),
-            '-' => Ok(Operation::Subtract),
-            '*' => Ok(Operation::Multiply),
-            '/' => Ok(Operation::Divide),
+            // Typographic minus sign, as typed on phones and copied from documents
+            '-' | '−' => Ok(Operation::Subtract),
+            '*' | '×' | 'x' | 'X' => Ok(Operation::Multiply),
+            '/' | '÷' => Ok(Operation::Divide),
+            '^' => Ok(Operation::Power),
+            '%' => Ok(Operation::Modulo),
+            '|' => Ok(Operation::Concatenate),
             c => Err(LexError::InvalidCharacter(c)),
         }
     }
 }
 
 #[derive(Copy, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Parenthesis {
     Open,
     Close,
@@ -69,37 +316,102 @@ impl TryFrom<char> for Parenthesis {
 
     fn try_from(input: char) -> Result<Parenthesis, Self::Error> {
         match input {
-            '(' => Ok(Parenthesis::Open),
-            ')' => Ok(Parenthesis::Close),
+            '(' | '[' => Ok(Parenthesis::Open),
+            ')' | ']' => Ok(Parenthesis::Close),
             c => Err(LexError::InvalidCharacter(c)),
         }
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum Token {
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TokenKind {
     Number(usize),
     Operation(Operation),
     Parenthesis(Parenthesis),
 }
 
+/// A byte range of the source string, from `start` up to but not including `end`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A token and, if it was lexed from a string, the region of the string it was read from. Tokens
+/// built by the solver or from an expression tree have no span. Spans are ignored when comparing
+/// or serializing tokens.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Token {
+    pub kind: TokenKind,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub span: Option<Span>,
+}
+
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Eq for Token {}
+
+impl From<TokenKind> for Token {
+    fn from(kind: TokenKind) -> Token {
+        Token { kind, span: None }
+    }
+}
+
+impl Token {
+    pub fn to_string_with(&self, style: Style) -> String {
+        match self.kind {
+            TokenKind::Number(n) => style.radix.format(n),
+            TokenKind::Operation(op) => op.symbol(style.symbols).to_string(),
+            TokenKind::Parenthesis(p) => p.to_string(),
+        }
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Token::Number(n) => write!(f, "{}", n),
-            Token::Operation(op) => write!(f, "{}", op),
-            Token::Parenthesis(p) => write!(f, "{}", p),
+        match self.kind {
+            TokenKind::Number(n) => write!(f, "{}", n),
+            TokenKind::Operation(op) => write!(f, "{}", op),
+            TokenKind::Parenthesis(p) => write!(f, "{}", p),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Expression(pub Vec<Token>);
 
 impl Expression {
     pub fn new() -> Expression {
         Expression(Vec::<Token>::new())
     }
+
+    pub fn to_string_with(&self, style: Style) -> String {
+        let characters = self
+            .0
+            .iter()
+            .map(|t| t.to_string_with(style))
+            .collect::<Vec<String>>();
+
+        characters.join(" ")
+    }
+
+    /// Check that every operation in the expression is one of `operations`.
+    pub fn uses_only(&self, operations: &[Operation]) -> bool {
+        self.0.iter().all(|token| match token.kind {
+            TokenKind::Operation(op) => operations.contains(&op),
+            _ => true,
+        })
+    }
 }
 
 impl fmt::Display for Expression {
@@ -117,105 +429,304 @@ impl FromStr for Expression {
     type Err = LexError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::lex(s)
+    }
+}
+
+impl Expression {
+    /// Split `s` into tokens, keeping the span of each so that errors can point back into `s`.
+    pub fn lex(s: &str) -> Result<Expression, LexError> {
+        Expression::lex_with_radix(s, Radix::Decimal)
+    }
+
+    /// Lex `s`, reading unprefixed numbers in `radix`. Numbers prefixed with `0b`, `0o` or `0x`
+    /// are always read in that radix, so `0x10` is a number rather than `0 × 10`.
+    pub fn lex_with_radix(s: &str, radix: Radix) -> Result<Expression, LexError> {
         let mut tokens = Vec::<Token>::new();
-        let mut num_buffer = Vec::<char>::new();
-        for (i, c) in s.trim().char_indices() {
-            if c.is_digit(BASE) {
-                num_buffer.push(c);
+        let mut chars = s.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            if c.is_digit(radix.value()) {
+                let (number, end) = lex_number(s, i, radix)?;
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+                tokens.push(Token {
+                    kind: TokenKind::Number(number),
+                    span: Some(Span { start: i, end }),
+                });
                 continue;
-            } else if num_buffer.len() > 0 {
-                tokens.push(Token::Number(
-                    num_buffer.iter().collect::<String>().parse().unwrap(),
-                ));
-                num_buffer = Vec::<char>::new();
             }
 
-            let token: Option<Token> = match c {
-                ' ' => None,
-                '(' | ')' => Some(Token::Parenthesis(Parenthesis::try_from(c).unwrap())),
-                '+' | '-' | '*' | '/' => Some(Token::Operation(Operation::try_from(c).unwrap())),
-                _ => return Err(LexError::InvalidCharacterAtIndex(i, c)),
+            let kind: Option<TokenKind> = if c.is_whitespace() {
+                None
+            } else if let Ok(p) = Parenthesis::try_from(c) {
+                Some(TokenKind::Parenthesis(p))
+            } else if let Ok(op) = Operation::try_from(c) {
+                Some(TokenKind::Operation(op))
+            } else {
+                return Err(LexError::InvalidCharacterAtIndex(i, c));
             };
 
-            match token {
-                Some(t) => tokens.push(t),
-                None => continue,
+            if let Some(kind) = kind {
+                tokens.push(Token {
+                    kind,
+                    span: Some(Span {
+                        start: i,
+                        end: i + c.len_utf8(),
+                    }),
+                });
             }
         }
 
-        if num_buffer.len() > 0 {
-            tokens.push(Token::Number(
-                num_buffer.iter().collect::<String>().parse().unwrap(),
-            ));
-        }
         Ok(Expression(tokens))
     }
 }
 
+/// Lex the number starting at `start`, which may carry a prefix overriding `radix`. Returns the
+/// number and the index just past it.
+fn lex_number(s: &str, start: usize, radix: Radix) -> Result<(usize, usize), LexError> {
+    let (radix, digits_start) = match Radix::from_prefix(&s[start..], radix) {
+        Some(prefixed) => (prefixed, start + prefixed.prefix().len()),
+        None => (radix, start),
+    };
+    let end = s[digits_start..]
+        .find(|c: char| !c.is_digit(radix.value()))
+        .map_or(s.len(), |length| digits_start + length);
+
+    let n = usize::from_str_radix(&s[digits_start..end], radix.value())
+        .map_err(|_| LexError::NumberTooLargeAtIndex(start, end))?;
+
+    Ok((n, end))
+}
+
 #[derive(Debug, PartialEq)]
 pub enum FixExpressionError {
-    InvalidFixExpression,
+    EmptyExpression,
+    /// An operation at this token index has fewer than two operands available.
+    StackUnderflow(usize),
+    /// This many values remain once every operation has been applied, instead of one.
+    LeftoverOperands(usize),
+    /// A parenthesis at this token index, which has no meaning in postfix notation.
+    UnexpectedParenthesis(usize),
+    /// A parenthesis at this token index has no matching partner.
+    UnmatchedParenthesis(usize),
+    /// A token at this index cannot appear at that point in an infix expression.
+    UnexpectedToken(usize),
+    /// The expression ends where an operand is expected.
+    UnexpectedEnd,
+    NonIntegerExponent,
+    ConcatenatedNonTile,
+    DivisionByZero,
+    Overflow,
+}
+
+impl fmt::Display for FixExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FixExpressionError::EmptyExpression => write!(f, "expression is empty"),
+            FixExpressionError::StackUnderflow(i) => {
+                write!(f, "operation at token {} is missing an operand", i)
+            }
+            FixExpressionError::LeftoverOperands(n) => {
+                write!(f, "{} values are left over without an operation", n)
+            }
+            FixExpressionError::UnexpectedParenthesis(i) => {
+                write!(f, "unexpected parenthesis at token {}", i)
+            }
+            FixExpressionError::UnmatchedParenthesis(i) => {
+                write!(f, "unmatched parenthesis at token {}", i)
+            }
+            FixExpressionError::UnexpectedToken(i) => write!(f, "unexpected token {}", i),
+            FixExpressionError::UnexpectedEnd => write!(f, "expression ends unexpectedly"),
+            FixExpressionError::NonIntegerExponent => write!(f, "exponent is not an integer"),
+            FixExpressionError::ConcatenatedNonTile => {
+                write!(f, "only tiles may be concatenated")
+            }
+            FixExpressionError::DivisionByZero => write!(f, "division by zero"),
+            FixExpressionError::Overflow => write!(f, "number is too large"),
+        }
+    }
+}
+
+impl std::error::Error for FixExpressionError {}
+
+/// A single operation performed while evaluating an expression, e.g. `100 + 3 = 103`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub operation: Operation,
+    pub lhs: Ratio<isize>,
+    pub rhs: Ratio<isize>,
+    pub result: Ratio<isize>,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Fractional operands are bracketed so that `6 / (1/14)` is not misread
+        let operand = |r: &Ratio<isize>| {
+            if r.is_integer() {
+                r.to_string()
+            } else {
+                format!("({})", r)
+            }
+        };
+
+        write!(
+            f,
+            "{} {} {} = {}",
+            operand(&self.lhs),
+            self.operation,
+            operand(&self.rhs),
+            self.result
+        )
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct PostfixExpression(pub Expression);
 
 impl PostfixExpression {
-    fn validate(&self) -> bool {
+    /// Check that the tokens form a well-formed postfix expression, without evaluating it.
+    pub fn validate(&self) -> bool {
         let mut op_count = 0;
         let mut num_count = 0;
 
         for token in &self.0 .0 {
-            match token {
-                Token::Parenthesis(_) => return false,
-                Token::Operation(_) => {
+            match token.kind {
+                TokenKind::Parenthesis(_) => return false,
+                TokenKind::Operation(_) => {
                     op_count += 1;
                     if num_count <= op_count {
                         return false;
                     }
                 }
-                Token::Number(_) => {
+                TokenKind::Number(_) => {
                     num_count += 1;
                 }
             }
         }
-        if op_count != num_count - 1 {
-            false
-        } else {
-            true
-        }
+        op_count + 1 == num_count
     }
 
     pub fn evaluate(&self) -> Result<Ratio<isize>, FixExpressionError> {
-        if !self.validate() {
-            return Err(FixExpressionError::InvalidFixExpression);
+        self.run().map(|(value, _)| value)
+    }
+
+    /// Every operation performed during evaluation, in the order it is performed.
+    pub fn steps(&self) -> Result<Vec<Step>, FixExpressionError> {
+        self.run().map(|(_, steps)| steps)
+    }
+
+    fn run(&self) -> Result<(Ratio<isize>, Vec<Step>), FixExpressionError> {
+        run(self.0 .0.iter().enumerate(), false)
+    }
+}
+
+impl fmt::Display for PostfixExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct PrefixExpression(pub Expression);
+
+impl fmt::Display for PrefixExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl PrefixExpression {
+    /// Check that the tokens form a well-formed prefix expression, without evaluating it.
+    pub fn validate(&self) -> bool {
+        let mut op_count = 0;
+        let mut num_count = 0;
+
+        // A prefix expression is a postfix expression read backwards, with operands swapped
+        for token in self.0 .0.iter().rev() {
+            match token.kind {
+                TokenKind::Parenthesis(_) => return false,
+                TokenKind::Operation(_) => {
+                    op_count += 1;
+                    if num_count <= op_count {
+                        return false;
+                    }
+                }
+                TokenKind::Number(_) => {
+                    num_count += 1;
+                }
+            }
         }
+        op_count + 1 == num_count
+    }
+
+    pub fn evaluate(&self) -> Result<Ratio<isize>, FixExpressionError> {
+        self.run().map(|(value, _)| value)
+    }
 
-        let mut stack = Vec::<Ratio<isize>>::new();
+    /// Every operation performed during evaluation, in the order it is performed.
+    pub fn steps(&self) -> Result<Vec<Step>, FixExpressionError> {
+        self.run().map(|(_, steps)| steps)
+    }
 
-        for token in self.0 .0.iter() {
-            match token {
-                Token::Number(n) => {
-                    stack.push(Ratio::<isize>::from_integer((*n).try_into().unwrap()));
+    fn run(&self) -> Result<(Ratio<isize>, Vec<Step>), FixExpressionError> {
+        run(self.0 .0.iter().enumerate().rev(), true)
+    }
+}
+
+/// Evaluate tokens in stack order. Postfix tokens are read forwards; prefix tokens are read
+/// backwards, which leaves each operation's left operand on top of the stack rather than its right.
+fn run<'a>(
+    tokens: impl Iterator<Item = (usize, &'a Token)>,
+    reversed: bool,
+) -> Result<(Ratio<isize>, Vec<Step>), FixExpressionError> {
+    let mut stack = Vec::<Ratio<isize>>::new();
+    let mut steps = Vec::<Step>::new();
+    // Tracks whether each stack entry is a raw tile, since only tiles may be concatenated
+    let mut is_tile = Vec::<bool>::new();
+
+    for (i, token) in tokens {
+        match token.kind {
+            TokenKind::Number(n) => {
+                let n = isize::try_from(n).map_err(|_| FixExpressionError::Overflow)?;
+                stack.push(Ratio::<isize>::from_integer(n));
+                is_tile.push(true);
+            }
+            TokenKind::Operation(op) => {
+                if stack.len() < 2 {
+                    return Err(FixExpressionError::StackUnderflow(i));
                 }
-                Token::Operation(op) => {
-                    let last_num = stack.pop().unwrap();
-                    let first_num = stack.pop().unwrap();
-
-                    let result = match op {
-                        Operation::Add => first_num + last_num,
-                        Operation::Subtract => first_num - last_num,
-                        Operation::Multiply => first_num * last_num,
-                        Operation::Divide => first_num / last_num,
-                    };
-
-                    stack.push(result);
+                let mut last_num = stack.pop().unwrap();
+                let mut first_num = stack.pop().unwrap();
+                let last_is_tile = is_tile.pop().unwrap();
+                let first_is_tile = is_tile.pop().unwrap();
+                if reversed {
+                    std::mem::swap(&mut first_num, &mut last_num);
                 }
-                Token::Parenthesis(_) => panic!("Unexpected parenthesis token found."),
+
+                if op == Operation::Concatenate && !(first_is_tile && last_is_tile) {
+                    return Err(FixExpressionError::ConcatenatedNonTile);
+                }
+                let result = op.checked_apply(&first_num, &last_num)?;
+
+                stack.push(result);
+                is_tile.push(false);
+                steps.push(Step {
+                    operation: op,
+                    lhs: first_num,
+                    rhs: last_num,
+                    result,
+                });
             }
+            TokenKind::Parenthesis(_) => return Err(FixExpressionError::UnexpectedParenthesis(i)),
         }
+    }
 
-        Ok(stack.first().unwrap().clone())
+    match stack.len() {
+        0 => Err(FixExpressionError::EmptyExpression),
+        1 => Ok((stack[0], steps)),
+        n => Err(FixExpressionError::LeftoverOperands(n)),
     }
 }
 
@@ -224,29 +735,175 @@ mod tests {
     use super::*;
     use test_case::test_case;
 
-    #[test_case("1+2", vec![Token::Number(1), Token::Operation(Operation::Add), Token::Number(2)]; "simple addition")]
-    #[test_case("12 + 34", vec![Token::Number(12), Token::Operation(Operation::Add), Token::Number(34)]; "double digit addition")]
-    #[test_case("1  *(2 -3) ", vec![Token::Number(1), Token::Operation(Operation::Multiply), Token::Parenthesis(Parenthesis::Open), Token::Number(2), Token::Operation(Operation::Subtract), Token::Number(3), Token::Parenthesis(Parenthesis::Close)]; "nested operation with unusual spacing")]
-    #[test_case("12 *(345/ 6789)", vec![Token::Number(12), Token::Operation(Operation::Multiply), Token::Parenthesis(Parenthesis::Open), Token::Number(345), Token::Operation(Operation::Divide), Token::Number(6789), Token::Parenthesis(Parenthesis::Close)]; "another nested operation with unusual spacing")]
-    #[test_case("1 23  345 +  + ", vec![Token::Number(1), Token::Number(23), Token::Number(345), Token::Operation(Operation::Add), Token::Operation(Operation::Add)]; "example postfix expression")]
-    fn expr_from_str_tests(input: &str, tokens: Vec<Token>) {
+    #[test_case("1+2", vec![TokenKind::Number(1), TokenKind::Operation(Operation::Add), TokenKind::Number(2)]; "simple addition")]
+    #[test_case("12 + 34", vec![TokenKind::Number(12), TokenKind::Operation(Operation::Add), TokenKind::Number(34)]; "double digit addition")]
+    #[test_case("1  *(2 -3) ", vec![TokenKind::Number(1), TokenKind::Operation(Operation::Multiply), TokenKind::Parenthesis(Parenthesis::Open), TokenKind::Number(2), TokenKind::Operation(Operation::Subtract), TokenKind::Number(3), TokenKind::Parenthesis(Parenthesis::Close)]; "nested operation with unusual spacing")]
+    #[test_case("12 *(345/ 6789)", vec![TokenKind::Number(12), TokenKind::Operation(Operation::Multiply), TokenKind::Parenthesis(Parenthesis::Open), TokenKind::Number(345), TokenKind::Operation(Operation::Divide), TokenKind::Number(6789), TokenKind::Parenthesis(Parenthesis::Close)]; "another nested operation with unusual spacing")]
+    #[test_case("1 23  345 +  + ", vec![TokenKind::Number(1), TokenKind::Number(23), TokenKind::Number(345), TokenKind::Operation(Operation::Add), TokenKind::Operation(Operation::Add)]; "example postfix expression")]
+    fn expr_from_str_tests(input: &str, tokens: Vec<TokenKind>) {
+        let tokens = tokens.into_iter().map(Token::from).collect();
         assert_eq!(Expression::from_str(input).unwrap(), Expression(tokens));
     }
 
+    #[test_case("2 ^ 3 % 5", vec![TokenKind::Number(2), TokenKind::Operation(Operation::Power), TokenKind::Number(3), TokenKind::Operation(Operation::Modulo), TokenKind::Number(5)]; "power and modulo")]
+    fn expr_from_str_extended_operations_tests(input: &str, tokens: Vec<TokenKind>) {
+        let tokens = tokens.into_iter().map(Token::from).collect();
+        assert_eq!(Expression::from_str(input).unwrap(), Expression(tokens));
+    }
+
+    #[test_case("1 2 +", &Operation::STANDARD, true; "standard operations")]
+    #[test_case("2 3 ^", &Operation::STANDARD, false; "power not standard")]
+    #[test_case("2 3 ^ 4 %", &Operation::ALL, true; "all operations")]
+    #[test_case("2 3 - 4 +", &[Operation::Add, Operation::Multiply], false; "subtract not allowed")]
+    fn uses_only_tests(input: &str, operations: &[Operation], expected: bool) {
+        assert_eq!(
+            Expression::from_str(input).unwrap().uses_only(operations),
+            expected
+        );
+    }
+
+    #[test_case("3 × 4", "3 * 4"; "multiplication sign")]
+    #[test_case("3x4 X 5", "3 * 4 * 5"; "letter x")]
+    #[test_case("8 ÷ 2", "8 / 2"; "division sign")]
+    #[test_case("8 − 2", "8 - 2"; "minus sign")]
+    #[test_case("[1 + 2] × (3 − 4)", "( 1 + 2 ) * ( 3 - 4 )"; "square brackets")]
+    fn expr_from_str_alias_tests(input: &str, expected: &str) {
+        assert_eq!(
+            Expression::from_str(input).unwrap(),
+            Expression::from_str(expected).unwrap()
+        );
+    }
+
+    #[test_case("( 100 + 3 ) * 7 - 8 / 2", "( 100 + 3 ) × 7 − 8 ÷ 2"; "infix")]
+    #[test_case("100 3 + 7 * 8 2 / -", "100 3 + 7 × 8 2 ÷ −"; "postfix")]
+    fn expr_to_typographic_str_tests(input: &str, expected: &str) {
+        let expression = Expression::from_str(input).unwrap();
+        let typographic = expression.to_string_with(Style {
+            symbols: Symbols::Typographic,
+            ..Style::default()
+        });
+        assert_eq!(typographic, expected);
+        assert_eq!(Expression::from_str(&typographic), Ok(expression));
+    }
+
+    #[test_case("0b1010 + 0b11", Radix::Decimal, vec![10, 3]; "binary prefix")]
+    #[test_case("0o17 0O7 *", Radix::Decimal, vec![15, 7]; "octal prefix")]
+    #[test_case("0xff - 0X1A", Radix::Decimal, vec![255, 26]; "hexadecimal prefix")]
+    #[test_case("1010 + 11", Radix::Binary, vec![10, 3]; "default binary")]
+    #[test_case("ff - 1a", Radix::Hexadecimal, vec![255, 26]; "default hexadecimal")]
+    #[test_case("0xA 10", Radix::Binary, vec![10, 2]; "prefix overrides default")]
+    #[test_case("0b1 0o1 0x10", Radix::Hexadecimal, vec![0xB1, 1, 16]; "prefix letters that are hexadecimal digits")]
+    fn lex_with_radix_tests(input: &str, radix: Radix, numbers: Vec<usize>) {
+        let expression = Expression::lex_with_radix(input, radix).unwrap();
+        let lexed = expression
+            .0
+            .iter()
+            .filter_map(|t| match t.kind {
+                TokenKind::Number(n) => Some(n),
+                _ => None,
+            })
+            .collect::<Vec<usize>>();
+        assert_eq!(lexed, numbers);
+    }
+
+    #[test_case("0x 5", "0 * 5"; "prefix without digits is multiplication")]
+    #[test_case("3x5", "3 * 5"; "letter x between decimals")]
+    fn lex_prefix_ambiguity_tests(input: &str, expected: &str) {
+        assert_eq!(
+            Expression::from_str(input).unwrap(),
+            Expression::from_str(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn bad_lex_prefix_without_digits() {
+        assert_eq!(
+            Expression::from_str("0b2"),
+            Err(LexError::InvalidCharacterAtIndex(1, 'b'))
+        )
+    }
+
+    #[test_case("( 100 + 3 ) * 7", Radix::Binary, "( 0b1100100 + 0b11 ) * 0b111"; "binary")]
+    #[test_case("( 100 + 3 ) * 7", Radix::Octal, "( 0o144 + 0o3 ) * 0o7"; "octal")]
+    #[test_case("( 100 + 3 ) * 7", Radix::Decimal, "( 100 + 3 ) * 7"; "decimal")]
+    #[test_case("( 100 + 3 ) * 7", Radix::Hexadecimal, "( 0x64 + 0x3 ) * 0x7"; "hexadecimal")]
+    fn radix_round_trip_tests(input: &str, radix: Radix, expected: &str) {
+        let expression = Expression::from_str(input).unwrap();
+        let rendered = expression.to_string_with(Style {
+            radix,
+            ..Style::default()
+        });
+        assert_eq!(rendered, expected);
+        assert_eq!(Expression::from_str(&rendered), Ok(expression.clone()));
+        assert_eq!(Expression::lex_with_radix(&rendered, radix), Ok(expression));
+    }
+
+    #[test_case("2", Radix::Binary)]
+    #[test_case("hex", Radix::Hexadecimal)]
+    #[test_case("Octal", Radix::Octal)]
+    fn radix_from_str(input: &str, expected: Radix) {
+        assert_eq!(Radix::from_str(input), Ok(expected));
+    }
+
+    #[test]
+    fn bad_lex_number_too_large_with_prefix() {
+        assert_eq!(
+            Expression::from_str("1 + 0x1FFFFFFFFFFFFFFFF"),
+            Err(LexError::NumberTooLargeAtIndex(4, 23))
+        )
+    }
+
     #[test]
     fn bad_lex_char() {
-        let input = "(1+ 2/ 3** a 51 x)";
+        let input = "(1+ 2/ 3** a 51 y)";
         assert_eq!(
             Expression::from_str(input),
             Err(LexError::InvalidCharacterAtIndex(11, 'a'))
         )
     }
 
+    #[test]
+    fn bad_lex_char_with_leading_whitespace() {
+        assert_eq!(
+            Expression::from_str("   1 + a"),
+            Err(LexError::InvalidCharacterAtIndex(7, 'a'))
+        )
+    }
+
+    #[test]
+    fn bad_lex_number_too_large() {
+        assert_eq!(
+            Expression::from_str("1 + 99999999999999999999999"),
+            Err(LexError::NumberTooLargeAtIndex(4, 27))
+        )
+    }
+
+    #[test_case(" 12 +(3) ", vec![(1, 3), (4, 5), (5, 6), (6, 7), (7, 8)]; "leading whitespace")]
+    #[test_case("100\t*\n7", vec![(0, 3), (4, 5), (6, 7)]; "tabs and newlines")]
+    fn lex_spans_tests(input: &str, spans: Vec<(usize, usize)>) {
+        let tokens = Expression::lex(input).unwrap().0;
+        let lexed_spans = tokens
+            .iter()
+            .map(|t| t.span.unwrap())
+            .collect::<Vec<Span>>();
+        assert_eq!(
+            lexed_spans
+                .iter()
+                .map(|span| (span.start, span.end))
+                .collect::<Vec<(usize, usize)>>(),
+            spans
+        );
+        for (t, span) in tokens.iter().zip(lexed_spans) {
+            assert_eq!(input[span.start..span.end], t.to_string());
+        }
+    }
+
     #[test_case("1+2", "1 + 2"; "simple addition")]
     #[test_case("12 + 34", "12 + 34"; "double digit addition")]
     #[test_case("1  *(2 -3) ", "1 * ( 2 - 3 )"; "nested operation with unusual spacing")]
     #[test_case("12 *(345/ 6789)  ", "12 * ( 345 / 6789 )"; "another nested operation with unusual spacing")]
     #[test_case("1 23  345 + + ", "1 23 345 + +"; "example postfix expression")]
+    #[test_case("1 2|3 *", "1 2 | 3 *"; "concatenation")]
     fn expr_to_str_tests(input: &str, expected: &str) {
         assert_eq!(
             Expression::from_str(input).unwrap().to_string(),
@@ -259,6 +916,7 @@ mod tests {
     #[test_case("12 34 +", true; "double digit addition")]
     #[test_case("1 2 3 - *", true; "nested operation")]
     #[test_case("12 345 6789 / *", true; "another nested operation")]
+    #[test_case("", false; "empty expression")]
     #[test_case("+ + 1 23 345", false; "postfix expression")]
     #[test_case("1 * 2 - 3", false; "simple infix expression")]
     #[test_case("1 * (2 - 3)", false; "nested infix expression")]
@@ -268,4 +926,115 @@ mod tests {
             expected,
         );
     }
+
+    #[test_case("2 3 ^", Ratio::from_integer(8); "power")]
+    #[test_case("2 0 2 - ^", Ratio::new(1, 4); "negative power")]
+    #[test_case("17 5 %", Ratio::from_integer(2); "modulo")]
+    #[test_case("3 2 ^ 4 % 10 *", Ratio::from_integer(10); "mixed operations")]
+    fn evaluate_extended_operations_tests(input: &str, expected: Ratio<isize>) {
+        assert_eq!(
+            PostfixExpression(Expression::from_str(input).unwrap()).evaluate(),
+            Ok(expected),
+        );
+    }
+
+    #[test_case("1 2 |", Ratio::from_integer(12); "concatenate two tiles")]
+    #[test_case("25 100 | 2 *", Ratio::from_integer(50200); "concatenate multi-digit tiles")]
+    #[test_case("1 0 |", Ratio::from_integer(10); "concatenate zero")]
+    #[test_case("10 0 |", Ratio::from_integer(100); "concatenate zero after multi-digit tile")]
+    fn evaluate_concatenation_tests(input: &str, expected: Ratio<isize>) {
+        assert_eq!(
+            PostfixExpression(Expression::from_str(input).unwrap()).evaluate(),
+            Ok(expected),
+        );
+    }
+
+    #[test_case("1 2 + 3 |"; "intermediate result on left")]
+    #[test_case("3 1 2 + |"; "intermediate result on right")]
+    #[test_case("1 2 | 3 |"; "concatenated result")]
+    fn evaluate_concatenated_non_tile_tests(input: &str) {
+        assert_eq!(
+            PostfixExpression(Expression::from_str(input).unwrap()).evaluate(),
+            Err(FixExpressionError::ConcatenatedNonTile),
+        );
+    }
+
+    #[test_case("100 3 + 7 *", vec!["100 + 3 = 103", "103 * 7 = 721"]; "integer steps")]
+    #[test_case("1 5 4 / - 8 *", vec!["5 / 4 = 5/4", "1 - (5/4) = -1/4", "(-1/4) * 8 = -2"]; "fractional and negative steps")]
+    #[test_case("7", vec![]; "single number")]
+    fn steps_tests(input: &str, expected: Vec<&str>) {
+        let steps = PostfixExpression(Expression::from_str(input).unwrap())
+            .steps()
+            .unwrap();
+        assert_eq!(
+            steps.iter().map(|s| s.to_string()).collect::<Vec<String>>(),
+            expected
+        );
+    }
+
+    #[test_case("9223372036854775807 1 +", FixExpressionError::Overflow; "addition overflow")]
+    #[test_case("4294967296 4294967296 *", FixExpressionError::Overflow; "multiplication overflow")]
+    #[test_case("100 100 ^", FixExpressionError::Overflow; "power overflow")]
+    #[test_case("18446744073709551615", FixExpressionError::Overflow; "number too large")]
+    #[test_case("7 3 3 - /", FixExpressionError::DivisionByZero; "division by zero")]
+    #[test_case("7 3 3 - %", FixExpressionError::DivisionByZero; "modulo by zero")]
+    #[test_case("3 3 - 1 2 - ^", FixExpressionError::DivisionByZero; "zero to a negative power")]
+    fn evaluate_arithmetic_error_tests(input: &str, expected: FixExpressionError) {
+        assert_eq!(
+            PostfixExpression(Expression::from_str(input).unwrap()).evaluate(),
+            Err(expected),
+        );
+    }
+
+    #[test_case("", FixExpressionError::EmptyExpression; "empty expression")]
+    #[test_case("   ", FixExpressionError::EmptyExpression; "whitespace only")]
+    #[test_case("+", FixExpressionError::StackUnderflow(0); "lone operation")]
+    #[test_case("1 2 + * 3", FixExpressionError::StackUnderflow(3); "underflow mid expression")]
+    #[test_case("+ + 1 23 345", FixExpressionError::StackUnderflow(0); "prefix expression")]
+    #[test_case("1 2 3 +", FixExpressionError::LeftoverOperands(2); "one leftover operand")]
+    #[test_case("1 2 3 4", FixExpressionError::LeftoverOperands(4); "no operations")]
+    #[test_case("1 * (2 - 3)", FixExpressionError::StackUnderflow(1); "infix expression")]
+    #[test_case("1 2 ( + )", FixExpressionError::UnexpectedParenthesis(2); "parenthesis")]
+    fn evaluate_structural_error_tests(input: &str, expected: FixExpressionError) {
+        assert_eq!(
+            PostfixExpression(Expression::from_str(input).unwrap()).evaluate(),
+            Err(expected),
+        );
+    }
+
+    #[test_case("1", true; "single number")]
+    #[test_case("+ 1 2", true; "simple addition")]
+    #[test_case("* 1 - 2 3", true; "nested operation")]
+    #[test_case("+ + 1 23 345", true; "example prefix expression")]
+    #[test_case("1 23 345 + +", false; "postfix expression")]
+    #[test_case("1 * (2 - 3)", false; "nested infix expression")]
+    #[test_case("", false; "empty expression")]
+    fn validate_prefix_tests(input: &str, expected: bool) {
+        assert_eq!(
+            PrefixExpression(Expression::from_str(input).unwrap()).validate(),
+            expected,
+        );
+    }
+
+    #[test_case("+ + 1 23 345", Ok(Ratio::from_integer(369)); "example prefix expression")]
+    #[test_case("- 10 4", Ok(Ratio::from_integer(6)); "operand order")]
+    #[test_case("/ 1 - 5 4", Ok(Ratio::from_integer(1)); "nested right operand")]
+    #[test_case("| 1 2", Ok(Ratio::from_integer(12)); "concatenation")]
+    #[test_case("| + 1 2 3", Err(FixExpressionError::ConcatenatedNonTile); "concatenated non tile")]
+    #[test_case("+ 1", Err(FixExpressionError::StackUnderflow(0)); "stack underflow")]
+    #[test_case("+ 1 2 3", Err(FixExpressionError::LeftoverOperands(2)); "leftover operands")]
+    fn evaluate_prefix_tests(input: &str, expected: Result<Ratio<isize>, FixExpressionError>) {
+        assert_eq!(
+            PrefixExpression(Expression::from_str(input).unwrap()).evaluate(),
+            expected,
+        );
+    }
+
+    #[test]
+    fn evaluate_non_integer_exponent() {
+        assert_eq!(
+            PostfixExpression(Expression::from_str("4 1 2 / ^").unwrap()).evaluate(),
+            Err(FixExpressionError::NonIntegerExponent),
+        );
+    }
 }
@@ -0,0 +1,484 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use num::rational::Ratio;
+
+use super::parse::{parse_any_notation, ParseError};
+use super::round::NumbersRound;
+use super::solver::SolverOptions;
+use super::verify::{verify, VerifyError};
+
+/// Points scored on the show for reaching `value` when aiming for `target`: 10 for hitting it
+/// exactly, 7 for being within 5, 5 for being within 10 and nothing otherwise.
+pub fn score(target: usize, value: isize) -> u32 {
+    let Ok(value) = usize::try_from(value) else {
+        return 0;
+    };
+
+    match target.abs_diff(value) {
+        0 => 10,
+        1..=5 => 7,
+        6..=10 => 5,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeclarationError {
+    Parse(ParseError),
+    Verify(VerifyError),
+    /// The working is valid but does not give the declared number.
+    WrongValue {
+        declared: isize,
+        actual: Ratio<isize>,
+    },
+}
+
+impl fmt::Display for DeclarationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeclarationError::Parse(e) => write!(f, "{}", e),
+            DeclarationError::Verify(e) => write!(f, "{}", e),
+            DeclarationError::WrongValue { declared, actual } => write!(
+                f,
+                "working comes to {} rather than the declared {}",
+                actual, declared
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DeclarationError {}
+
+impl From<ParseError> for DeclarationError {
+    fn from(error: ParseError) -> DeclarationError {
+        DeclarationError::Parse(error)
+    }
+}
+
+impl From<VerifyError> for DeclarationError {
+    fn from(error: VerifyError) -> DeclarationError {
+        DeclarationError::Verify(error)
+    }
+}
+
+/// Parse a player's working, written in any notation, and check that it is valid for the round,
+/// returning its value.
+pub fn check_working(
+    numbers_round: &NumbersRound,
+    working: &str,
+    options: &SolverOptions,
+) -> Result<Ratio<isize>, DeclarationError> {
+    let solution = parse_any_notation(working)?;
+    Ok(verify(numbers_round, &solution, options)?)
+}
+
+/// Check that a player's working is valid and gives the number they declared.
+pub fn check_declaration(
+    numbers_round: &NumbersRound,
+    declared: isize,
+    working: &str,
+    options: &SolverOptions,
+) -> Result<(), DeclarationError> {
+    let value = check_working(numbers_round, working, options)?;
+
+    if !value.is_integer() || value.to_integer() != declared {
+        return Err(DeclarationError::WrongValue {
+            declared,
+            actual: value,
+        });
+    }
+
+    Ok(())
+}
+
+/// Check a player's declared number against their working and return the points it scores.
+/// Invalid working scores nothing, so callers may treat an error as zero.
+pub fn score_declaration(
+    numbers_round: &NumbersRound,
+    declared: isize,
+    working: &str,
+    options: &SolverOptions,
+) -> Result<u32, DeclarationError> {
+    check_declaration(numbers_round, declared, working, options)?;
+    Ok(score(numbers_round.target, declared))
+}
+
+/// A number a player claims to have reached, and the working that shows it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Declaration {
+    pub value: isize,
+    pub working: String,
+}
+
+/// How one player fared in a round.
+#[derive(Debug, PartialEq)]
+pub struct PlayerRound {
+    /// The declaration, or `None` if the player did not declare.
+    pub declaration: Option<Declaration>,
+    /// Why the declaration was rejected, if it was.
+    pub error: Option<DeclarationError>,
+    pub points: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RoundResult {
+    pub numbers_round: NumbersRound,
+    /// One entry per player, in the order players were given to the session.
+    pub players: Vec<PlayerRound>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SessionError {
+    /// Every round of the session has already been played.
+    Finished,
+    WrongNumberOfDeclarations {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Finished => write!(f, "every round has been played"),
+            SessionError::WrongNumberOfDeclarations { expected, actual } => write!(
+                f,
+                "expected {} declarations but received {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Standing {
+    pub player: String,
+    pub points: u32,
+}
+
+/// A match of several numbers rounds between local players, scored as on the show: in each
+/// round only the players whose valid declarations are closest to the target score.
+#[derive(Debug)]
+pub struct Session {
+    players: Vec<String>,
+    rounds: usize,
+    options: SolverOptions,
+    totals: Vec<u32>,
+    history: Vec<RoundResult>,
+}
+
+impl Session {
+    pub fn new(players: Vec<String>, rounds: usize, options: SolverOptions) -> Session {
+        Session {
+            totals: vec![0; players.len()],
+            players,
+            rounds,
+            options,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    /// Running totals, in the same order as [`Session::players`].
+    pub fn totals(&self) -> &[u32] {
+        &self.totals
+    }
+
+    /// Rounds played so far, oldest first.
+    pub fn history(&self) -> &[RoundResult] {
+        &self.history
+    }
+
+    pub fn rounds_remaining(&self) -> usize {
+        self.rounds - self.history.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.rounds_remaining() == 0
+    }
+
+    /// Score one round from each player's declaration, given in player order, and add the
+    /// points to the running totals.
+    pub fn play_round(
+        &mut self,
+        numbers_round: NumbersRound,
+        declarations: Vec<Option<Declaration>>,
+    ) -> Result<&RoundResult, SessionError> {
+        if self.is_finished() {
+            return Err(SessionError::Finished);
+        }
+        if declarations.len() != self.players.len() {
+            return Err(SessionError::WrongNumberOfDeclarations {
+                expected: self.players.len(),
+                actual: declarations.len(),
+            });
+        }
+
+        let mut players = declarations
+            .into_iter()
+            .map(|declaration| {
+                let error = declaration.as_ref().and_then(|d| {
+                    check_declaration(&numbers_round, d.value, &d.working, &self.options).err()
+                });
+                PlayerRound {
+                    declaration,
+                    error,
+                    points: 0,
+                }
+            })
+            .collect::<Vec<PlayerRound>>();
+
+        let valid_distance = |player: &PlayerRound| match (&player.declaration, &player.error) {
+            (Some(d), None) => usize::try_from(d.value)
+                .ok()
+                .map(|value| value.abs_diff(numbers_round.target)),
+            _ => None,
+        };
+        let closest = players.iter().filter_map(valid_distance).min();
+
+        for (i, player) in players.iter_mut().enumerate() {
+            if let (Some(distance), Some(d)) = (valid_distance(player), &player.declaration) {
+                if Some(distance) == closest {
+                    player.points = score(numbers_round.target, d.value);
+                    self.totals[i] += player.points;
+                }
+            }
+        }
+
+        self.history.push(RoundResult {
+            numbers_round,
+            players,
+        });
+        Ok(self.history.last().unwrap())
+    }
+
+    /// Players ordered by total points, highest first. Tied players keep their original order.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings = self
+            .players
+            .iter()
+            .zip(self.totals.iter())
+            .map(|(player, points)| Standing {
+                player: player.clone(),
+                points: *points,
+            })
+            .collect::<Vec<Standing>>();
+        standings.sort_by_key(|standing| Reverse(standing.points));
+        standings
+    }
+
+    /// The players with the most points, several if they are tied.
+    pub fn winners(&self) -> Vec<&str> {
+        let best = self.totals.iter().max().copied().unwrap_or(0);
+        self.players
+            .iter()
+            .zip(self.totals.iter())
+            .filter(|(_, points)| **points == best)
+            .map(|(player, _)| player.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(615, 615, 10; "exact")]
+    #[test_case(615, 610, 7; "five below")]
+    #[test_case(615, 620, 7; "five above")]
+    #[test_case(615, 606, 5; "nine below")]
+    #[test_case(615, 625, 5; "ten above")]
+    #[test_case(615, 626, 0; "eleven above")]
+    #[test_case(5, -5, 0; "negative")]
+    fn score_tests(target: usize, value: isize, expected: u32) {
+        assert_eq!(score(target, value), expected);
+    }
+
+    fn round() -> NumbersRound {
+        NumbersRound {
+            numbers: vec![25, 50, 3, 7, 2, 5],
+            target: 615,
+        }
+    }
+
+    #[test_case("(50 / (7 + 3)) * (25 * 5 - 2)", Ok(Ratio::from_integer(615)); "valid")]
+    #[test_case("25 * 7 * 3", Ok(Ratio::from_integer(525)); "valid but not the target")]
+    #[test_case("(50 - 25) * 25", Err(DeclarationError::Verify(VerifyError::UnavailableTile(25))); "bad tile")]
+    #[test_case("(50 - 25", Err(DeclarationError::Parse(parse_any_notation("(50 - 25").unwrap_err())); "unparseable")]
+    fn check_working_tests(working: &str, expected: Result<Ratio<isize>, DeclarationError>) {
+        assert_eq!(
+            check_working(&round(), working, &SolverOptions::default()),
+            expected
+        );
+    }
+
+    #[test_case(615, "(50 / (7 + 3)) * (25 * 5 - 2)", 10; "exact")]
+    #[test_case(615, "50 7 3 + / 25 5 * 2 - *", 10; "exact in postfix")]
+    #[test_case(620, "25 * (7 * 3 + 2) + 50 - 5", 7; "within five")]
+    #[test_case(625, "25 * (7 * 3 + 2) + 50", 5; "within ten")]
+    #[test_case(525, "25 * 7 * 3", 0; "too far")]
+    fn score_declaration_tests(declared: isize, working: &str, expected: u32) {
+        assert_eq!(
+            score_declaration(&round(), declared, working, &SolverOptions::default()),
+            Ok(expected)
+        );
+    }
+
+    #[test_case(610, "(50 - 25) * 25", DeclarationError::Verify(VerifyError::UnavailableTile(25)); "reused tile")]
+    #[test_case(615, "25 * 7 * 3", DeclarationError::WrongValue { declared: 615, actual: Ratio::from_integer(525) }; "wrong value")]
+    fn score_declaration_errors(declared: isize, working: &str, expected: DeclarationError) {
+        assert_eq!(
+            score_declaration(&round(), declared, working, &SolverOptions::default()),
+            Err(expected)
+        );
+    }
+
+    #[test]
+    fn fractional_working_is_reported_exactly() {
+        let options = SolverOptions {
+            allow_fractional_intermediate_values: true,
+            ..SolverOptions::default()
+        };
+        let error = score_declaration(&round(), 3, "7 / 2", &options).unwrap_err();
+
+        assert_eq!(
+            error,
+            DeclarationError::WrongValue {
+                declared: 3,
+                actual: Ratio::new(7, 2)
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "working comes to 7/2 rather than the declared 3"
+        );
+    }
+
+    #[test]
+    fn score_declaration_unparseable() {
+        assert!(matches!(
+            score_declaration(&round(), 615, "25 * * 3", &SolverOptions::default()),
+            Err(DeclarationError::Parse(_))
+        ));
+    }
+
+    fn declaration(value: isize, working: &str) -> Option<Declaration> {
+        Some(Declaration {
+            value,
+            working: working.to_string(),
+        })
+    }
+
+    fn session(rounds: usize) -> Session {
+        Session::new(
+            vec![
+                "Rachel".to_string(),
+                "Nick".to_string(),
+                "Susie".to_string(),
+            ],
+            rounds,
+            SolverOptions::default(),
+        )
+    }
+
+    #[test]
+    fn closest_valid_declaration_wins() {
+        let mut session = session(1);
+        let result = session
+            .play_round(
+                round(),
+                vec![
+                    declaration(620, "25 * (7 * 3 + 2) + 50 - 5"),
+                    declaration(615, "25 * 25"),
+                    declaration(625, "25 * (7 * 3 + 2) + 50"),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            result
+                .players
+                .iter()
+                .map(|p| p.points)
+                .collect::<Vec<u32>>(),
+            vec![7, 0, 0]
+        );
+        assert_eq!(
+            result.players[1].error,
+            Some(DeclarationError::Verify(VerifyError::UnavailableTile(25)))
+        );
+        assert_eq!(session.totals(), &[7, 0, 0]);
+        assert!(session.is_finished());
+    }
+
+    #[test]
+    fn tied_declarations_both_score() {
+        let mut session = session(2);
+        session
+            .play_round(
+                round(),
+                vec![
+                    declaration(615, "(50 / (7 + 3)) * (25 * 5 - 2)"),
+                    None,
+                    declaration(615, "50 7 3 + / 25 5 * 2 - *"),
+                ],
+            )
+            .unwrap();
+        session
+            .play_round(
+                round(),
+                vec![
+                    None,
+                    declaration(625, "25 * (7 * 3 + 2) + 50"),
+                    declaration(525, "25 * 7 * 3"),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(session.totals(), &[10, 5, 10]);
+        assert_eq!(session.winners(), vec!["Rachel", "Susie"]);
+        assert_eq!(
+            session.standings(),
+            vec![
+                Standing {
+                    player: "Rachel".to_string(),
+                    points: 10
+                },
+                Standing {
+                    player: "Susie".to_string(),
+                    points: 10
+                },
+                Standing {
+                    player: "Nick".to_string(),
+                    points: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn session_errors() {
+        let mut session = session(1);
+        assert_eq!(
+            session.play_round(round(), vec![None]).unwrap_err(),
+            SessionError::WrongNumberOfDeclarations {
+                expected: 3,
+                actual: 1
+            }
+        );
+        session.play_round(round(), vec![None, None, None]).unwrap();
+        assert_eq!(
+            session
+                .play_round(round(), vec![None, None, None])
+                .unwrap_err(),
+            SessionError::Finished
+        );
+    }
+}
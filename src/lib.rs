@@ -1,3 +1,16 @@
-mod expr;
+pub mod ast;
+pub mod batch;
+pub mod deck;
+pub mod diagnostic;
+pub mod equivalence;
+pub mod expr;
+pub mod game;
+pub mod parse;
+pub mod render;
+pub mod report;
 pub mod round;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod simplify;
 pub mod solver;
+pub mod verify;
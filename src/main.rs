@@ -1,28 +1,597 @@
-use clap::Parser;
-use std::time::Instant;
-use vorderman::round::NumbersRound;
-use vorderman::solver::find_solution;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use vorderman::ast::Expr;
+use vorderman::batch::{solve_batch, BatchRecord};
+use vorderman::deck::TileDeck;
+use vorderman::diagnostic::Diagnostic;
+use vorderman::expr::{LexError, Operation, Radix, Style, Symbols};
+use vorderman::game::{score_declaration, Declaration, DeclarationError, Session};
+use vorderman::render::{search_tree_to_dot, to_dot, to_latex, to_mathml, to_tree};
+#[cfg(feature = "serde")]
+use vorderman::report::Report;
+use vorderman::round::{NumbersRound, NumbersRoundError, RoundRules};
+use vorderman::solver::{search, trace_search, SearchResult, SolverOptions};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Postfix notation, styled by --typographic and --radix.
+    Text,
+    Latex,
+    Mathml,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Output {
+    /// Sentences for people to read.
+    Text,
+    /// A single JSON object for scripts, see `vorderman::report::Report`.
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum BatchOutput {
+    Csv,
+    /// One JSON object per line, see `vorderman::batch::BatchRecord`.
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Rules {
+    /// The show's tiles and targets from 100 to 999.
+    Classic,
+    /// The show's tiles and any target from 1 to 999.
+    Practice,
+}
+
+impl Rules {
+    fn round_rules(self) -> RoundRules {
+        match self {
+            Rules::Classic => RoundRules::classic(),
+            Rules::Practice => RoundRules::practice(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Operations(Vec<Operation>);
+
+fn parse_operations(s: &str) -> Result<Operations, LexError> {
+    s.chars()
+        .map(Operation::try_from)
+        .collect::<Result<Vec<Operation>, LexError>>()
+        .map(Operations)
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    let seconds = s
+        .parse::<f64>()
+        .map_err(|_| format!("{} is not a number of seconds", s))?;
+    Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string())
+}
+
+#[derive(clap::Args, Debug)]
+struct SolverArgs {
+    /// Operations the solver may use, e.g. "+*" or "+-*/^%|" ("|" concatenates tiles).
+    #[arg(short, long, default_value = "+-*/", value_parser = parse_operations)]
+    operations: Operations,
+
+    /// Allow intermediate values below zero.
+    #[arg(long)]
+    negatives: bool,
+
+    /// Fall back to fractional intermediate values if no integer solution exists.
+    #[arg(long)]
+    fractions: bool,
+}
+
+impl SolverArgs {
+    fn options(&self) -> SolverOptions {
+        SolverOptions {
+            operations: self.operations.0.clone(),
+            allow_negative_intermediate_values: self.negatives,
+            ..SolverOptions::default()
+        }
+    }
+
+    /// Search for a solution, retrying with fractional intermediate values if `--fractions` is
+    /// set and there is no integer solution. Also returns the options of the final search.
+    fn solve(
+        &self,
+        numbers_round: &NumbersRound,
+        timeout: Option<Duration>,
+    ) -> (SearchResult, SolverOptions) {
+        let now = Instant::now();
+        let options = SolverOptions {
+            timeout,
+            ..self.options()
+        };
+        let result = search(numbers_round.clone(), &options, true);
+        if !result.solutions.is_empty() || !self.fractions || result.timed_out {
+            return (result, options);
+        }
+
+        let fractional_options = SolverOptions {
+            allow_fractional_intermediate_values: true,
+            timeout: timeout.map(|t| t.saturating_sub(now.elapsed())),
+            ..options
+        };
+        let mut fractional_result = search(numbers_round.clone(), &fractional_options, true);
+        fractional_result.nodes_visited += result.nodes_visited;
+        (fractional_result, fractional_options)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Solve rounds read one per line, as "25 50 3 7 2 5 -> 615", CSV or JSON.
+    Batch(BatchArgs),
+    /// Play a round against the clock, then have your working checked and scored.
+    Play(PlayArgs),
+    /// Play a match of several rounds between players sharing one terminal.
+    Match(MatchArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct MatchArgs {
+    /// Names of the players, separated by commas.
+    #[arg(short, long, value_delimiter = ',', required = true)]
+    players: Vec<String>,
+
+    /// Number of numbers rounds to play.
+    #[arg(short, long, default_value_t = 3)]
+    rounds: usize,
+
+    /// Number of small tiles to deal each round.
+    #[arg(short, long, default_value_t = 4)]
+    smalls: u8,
+
+    /// Rules to deal rounds under.
+    #[arg(long, value_enum, default_value_t = Rules::Classic)]
+    rules: Rules,
+
+    /// Seconds on the clock.
+    #[arg(short, long, default_value_t = 30)]
+    time: u64,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct PlayArgs {
+    /// Number of small tiles to deal.
+    #[arg(short, long, default_value_t = 4)]
+    smalls: u8,
+
+    /// Rules to deal rounds under.
+    #[arg(long, value_enum, default_value_t = Rules::Classic)]
+    rules: Rules,
+
+    /// Seconds on the clock.
+    #[arg(short, long, default_value_t = 30)]
+    time: u64,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct BatchArgs {
+    /// File to read rounds from. Reads standard input if omitted.
+    input: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = BatchOutput::Csv)]
+    output: BatchOutput,
+
+    /// Number of rounds to solve in parallel.
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Give up on a round after this many seconds.
+    #[arg(long, value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+
+    /// Report rounds that could not have been dealt under these rules instead of solving them.
+    #[arg(long, value_enum)]
+    rules: Option<Rules>,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
 
 /// Generate and solve a random numbers round.
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    #[arg(short, long)]
-    smalls: u8,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(short, long, required = true)]
+    smalls: Option<u8>,
+
+    /// Rules to deal rounds under.
+    #[arg(long, value_enum, default_value_t = Rules::Classic)]
+    rules: Rules,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+
+    /// Print the solution with typographic symbols such as × and ÷.
+    #[arg(long)]
+    typographic: bool,
+
+    /// Radix to print numbers in: 2, 8, 10 or 16. Concatenation ("|") requires radix 10.
+    #[arg(long, default_value = "10")]
+    radix: Radix,
+
+    /// How to print the solution.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Print text, or a JSON report that ignores the formatting options.
+    #[arg(long, value_enum, default_value_t = Output::Text)]
+    output: Output,
+
+    /// Also draw the solution as a tree of operations.
+    #[arg(long)]
+    tree: bool,
+
+    /// Write the solution's expression tree to this file in Graphviz DOT format.
+    #[arg(long)]
+    dot: Option<PathBuf>,
+
+    /// Write the tree of partial expressions the solver explored to this file in DOT format.
+    #[arg(long)]
+    search_dot: Option<PathBuf>,
+
+    /// Maximum number of nodes to write with --search-dot.
+    #[arg(long, default_value_t = 10_000)]
+    search_dot_limit: usize,
+}
+
+fn batch(args: &BatchArgs) {
+    let input = match &args.input {
+        Some(path) => fs::read_to_string(path),
+        None => io::read_to_string(io::stdin()),
+    };
+    let input = input.unwrap_or_else(|e| {
+        let source = args
+            .input
+            .as_ref()
+            .map_or("standard input".to_string(), |path| {
+                path.display().to_string()
+            });
+        eprintln!("error: could not read {}: {}", source, e);
+        process::exit(1);
+    });
+    let rules = args.rules.map(Rules::round_rules);
+
+    let records = solve_batch(&input, args.jobs, rules.as_ref(), |numbers_round| {
+        args.solver.solve(&numbers_round, args.timeout).0
+    });
+
+    match args.output {
+        BatchOutput::Csv => {
+            println!("{}", BatchRecord::CSV_HEADER);
+            for record in records {
+                println!("{}", record.to_csv_row());
+            }
+        }
+        #[cfg(feature = "serde")]
+        BatchOutput::Json => {
+            for record in records {
+                println!("{}", serde_json::to_string(&record).unwrap());
+            }
+        }
+    }
+}
+
+/// Read standard input on another thread, so that waiting for a line can time out.
+fn stdin_lines() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+fn prompt(lines: &Receiver<String>, message: &str) -> String {
+    print!("{}", message);
+    io::stdout().flush().unwrap();
+    lines.recv().unwrap_or_default()
+}
+
+/// Show the round, then run the clock until it expires or a line is entered.
+fn countdown(lines: &Receiver<String>, numbers_round: &NumbersRound, time: u64) {
+    let tiles = numbers_round
+        .numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>();
+    println!("Tiles:  {}", tiles.join(" "));
+    println!("Target: {}", numbers_round.target);
+    println!("Press Enter to stop the clock early.");
+
+    let deadline = Instant::now().checked_add(Duration::from_secs(time));
+    loop {
+        let Some(deadline) = deadline else {
+            // A clock too long to represent never runs out, so only Enter stops it
+            lines.recv().ok();
+            break;
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        print!("\r{:>3} seconds remaining ", remaining.as_secs_f32().ceil());
+        io::stdout().flush().unwrap();
+        match lines.recv_timeout(remaining.min(Duration::from_secs(1))) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            Ok(_) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    println!("\rTime's up!{}", " ".repeat(16));
+}
+
+/// Ask for a declared number and the working for it, or `None` if no number is given.
+fn read_declaration(lines: &Receiver<String>, name: Option<&str>) -> Option<Declaration> {
+    let owner = name.map_or("Your".to_string(), |name| format!("{}'s", name));
+    let value = prompt(lines, &format!("{} number: ", owner))
+        .trim()
+        .parse::<isize>()
+        .ok()?;
+    let working = prompt(lines, &format!("{} working: ", owner));
+    Some(Declaration { value, working })
+}
+
+fn explain_error(error: &DeclarationError, working: &str) {
+    match error {
+        DeclarationError::Parse(e) => {
+            println!(
+                "{}",
+                Diagnostic::from_parse_error(e, working).render(working)
+            )
+        }
+        e => println!("{}.", e),
+    }
+}
+
+/// Deal a round under `rules`, exiting with a usage error if it can't have `smalls` small tiles.
+fn deal(rules: &RoundRules, smalls: u8) -> NumbersRound {
+    NumbersRound::with_rules(rules, smalls).unwrap_or_else(|e| smalls_error(smalls, e))
+}
+
+fn smalls_error(smalls: u8, error: NumbersRoundError) -> ! {
+    Args::command()
+        .error(
+            ErrorKind::ValueValidation,
+            format!("invalid value '{}' for '--smalls': {}", smalls, error),
+        )
+        .exit()
+}
+
+fn reveal_solution(solver: &SolverArgs, numbers_round: &NumbersRound) {
+    let (result, _) = solver.solve(numbers_round, None);
+    match (result.solutions.first(), result.closest) {
+        (Some(solution), _) => {
+            println!("Solution: {}", Expr::try_from(solution).unwrap());
+        }
+        (None, Some(closest)) => println!(
+            "The target can't be reached. Closest: {} = {}",
+            Expr::try_from(&closest).unwrap(),
+            closest.evaluate().unwrap()
+        ),
+        (None, None) => println!("The target can't be reached."),
+    }
+}
+
+fn play(args: &PlayArgs) {
+    let numbers_round = deal(&args.rules.round_rules(), args.smalls);
+    let options = args.solver.options();
+    let lines = stdin_lines();
+
+    countdown(&lines, &numbers_round, args.time);
+
+    match read_declaration(&lines, None) {
+        Some(Declaration { value, working }) => {
+            match score_declaration(&numbers_round, value, &working, &options) {
+                Ok(points) => println!("{} scores {} points.", value, points),
+                Err(e) => {
+                    explain_error(&e, &working);
+                    println!("No points.");
+                }
+            }
+        }
+        None => println!("No declaration. No points."),
+    }
+
+    reveal_solution(&args.solver, &numbers_round);
+}
+
+fn play_match(args: &MatchArgs) {
+    let mut session = Session::new(args.players.clone(), args.rounds, args.solver.options());
+    let rules = args.rules.round_rules();
+    let mut deck = TileDeck::from_rules(&rules);
+    let n_small = usize::from(args.smalls);
+    let n_big = rules
+        .bigs_for(n_small)
+        .unwrap_or_else(|e| smalls_error(args.smalls, e));
+    let lines = stdin_lines();
+
+    while !session.is_finished() {
+        println!(
+            "\nRound {} of {}",
+            args.rounds - session.rounds_remaining() + 1,
+            args.rounds
+        );
+        // Tiles stay out of the deck until it runs short, as they would across rounds on the show
+        let numbers_round = deck.deal_round(n_big, n_small).unwrap_or_else(|_| {
+            deck.reshuffle();
+            deck.deal_round(n_big, n_small).unwrap()
+        });
+        countdown(&lines, &numbers_round, args.time);
+
+        let declarations = args
+            .players
+            .iter()
+            .map(|player| read_declaration(&lines, Some(player)))
+            .collect();
+        let result = session
+            .play_round(numbers_round.clone(), declarations)
+            .unwrap();
+
+        for (player, outcome) in args.players.iter().zip(result.players.iter()) {
+            match (&outcome.declaration, &outcome.error) {
+                (None, _) => println!("{} did not declare.", player),
+                (Some(declaration), Some(e)) => {
+                    println!("{} declared {}, but:", player, declaration.value);
+                    explain_error(e, &declaration.working);
+                }
+                (Some(declaration), None) => println!(
+                    "{} declared {} and scores {} points.",
+                    player, declaration.value, outcome.points
+                ),
+            }
+        }
+        reveal_solution(&args.solver, &numbers_round);
+
+        let totals = args
+            .players
+            .iter()
+            .zip(session.totals())
+            .map(|(player, points)| format!("{} {}", player, points))
+            .collect::<Vec<String>>();
+        println!("Scores: {}", totals.join(", "));
+    }
+
+    println!("\nFinal standings:");
+    for (i, standing) in session.standings().iter().enumerate() {
+        println!(
+            "{}. {} ({} points)",
+            i + 1,
+            standing.player,
+            standing.points
+        );
+    }
+    match session.winners().as_slice() {
+        [winner] => println!("{} wins!", winner),
+        winners => println!("It's a tie between {}.", winners.join(" and ")),
+    }
+}
+
+/// Write `contents` to `path`, or report why it could not be written and exit.
+fn write_file(path: &Path, contents: &str) {
+    if let Err(e) = fs::write(path, contents) {
+        eprintln!("error: could not write {}: {}", path.display(), e);
+        process::exit(1);
+    }
 }
 
 fn main() {
     let args = Args::parse();
 
-    let numbers_round = NumbersRound::new(args.smalls).unwrap();
+    // Concatenation joins decimal digits, so its solutions read wrongly in any other radix
+    if args.radix != Radix::Decimal && args.solver.operations.0.contains(&Operation::Concatenate) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "concatenation (\"|\" in --operations) requires --radix 10",
+            )
+            .exit();
+    }
+
+    match &args.command {
+        Some(Command::Batch(batch_args)) => return batch(batch_args),
+        Some(Command::Play(play_args)) => return play(play_args),
+        Some(Command::Match(match_args)) => return play_match(match_args),
+        None => {}
+    }
+
+    let numbers_round = deal(&args.rules.round_rules(), args.smalls.unwrap());
 
     let now = Instant::now();
-    let solution = find_solution(numbers_round, false);
-    let time_taken = now.elapsed().as_secs_f32();
+    let (result, final_options) = args.solver.solve(&numbers_round, None);
+    let elapsed = now.elapsed();
+    let time_taken = elapsed.as_secs_f32();
+
+    if let Some(path) = &args.search_dot {
+        let tree = trace_search(
+            numbers_round.clone(),
+            &final_options,
+            true,
+            args.search_dot_limit,
+        );
+        write_file(path, &search_tree_to_dot(&tree));
+    }
+
+    let solution = result.solutions.first().cloned();
+    if let (Some(path), Some(s)) = (&args.dot, &solution) {
+        write_file(path, &to_dot(s).unwrap());
+    }
+
+    match args.output {
+        Output::Text => {}
+        #[cfg(feature = "serde")]
+        Output::Json => {
+            let report = Report::new(&numbers_round, &result, elapsed);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return;
+        }
+    }
+
+    println!("Round: {}", numbers_round.to_string_in(args.radix));
+
+    let style = Style {
+        symbols: if args.typographic {
+            Symbols::Typographic
+        } else {
+            Symbols::Ascii
+        },
+        radix: args.radix,
+    };
 
     match solution {
         Some(s) => {
-            println!("Found solution: {} in {} seconds.", s.0, time_taken,);
+            let rendered = match args.format {
+                Format::Text => s.0.to_string_with(style),
+                Format::Latex => to_latex(&s).unwrap(),
+                Format::Mathml => to_mathml(&s).unwrap(),
+            };
+            println!("Found solution: {} in {} seconds.", rendered, time_taken);
+            if args.tree {
+                println!("{}", to_tree(&s, style).unwrap());
+            }
+
+            let steps = s.steps().unwrap();
+            if steps.iter().any(|step| !step.result.is_integer()) {
+                println!("This solution requires fractional intermediate values:");
+                for step in steps {
+                    if step.result.is_integer() {
+                        println!("    {}", step);
+                    } else {
+                        println!("  * {}", step);
+                    }
+                }
+            }
         }
         None => {
             println!(
@@ -0,0 +1,206 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::ast::Expr;
+use super::expr::{Expression, FixExpressionError, LexError, PostfixExpression, PrefixExpression};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Notation {
+    Infix,
+    /// Reverse Polish notation, e.g. `1 2 +`.
+    Postfix,
+    /// Polish notation, e.g. `+ 1 2`.
+    Prefix,
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Notation::Infix => "infix",
+            Notation::Postfix => "postfix",
+            Notation::Prefix => "prefix",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "infix" => Ok(Notation::Infix),
+            "postfix" | "rpn" => Ok(Notation::Postfix),
+            "prefix" | "polish" => Ok(Notation::Prefix),
+            _ => Err(format!("unknown notation: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Lex(LexError),
+    Expression(FixExpressionError),
+    /// The tokens are not valid in any notation; holds why each notation was rejected.
+    Unrecognised(Vec<(Notation, FixExpressionError)>),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Lex(e) => write!(f, "{}", e),
+            ParseError::Expression(e) => write!(f, "{}", e),
+            ParseError::Unrecognised(errors) => {
+                let reasons = errors
+                    .iter()
+                    .map(|(n, e)| format!("as {}: {}", n, e))
+                    .collect::<Vec<String>>();
+                write!(f, "expression is not valid ({})", reasons.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<LexError> for ParseError {
+    fn from(error: LexError) -> ParseError {
+        ParseError::Lex(error)
+    }
+}
+
+impl From<FixExpressionError> for ParseError {
+    fn from(error: FixExpressionError) -> ParseError {
+        ParseError::Expression(error)
+    }
+}
+
+/// Lex `input` and interpret the tokens in the given notation.
+pub fn parse(input: &str, notation: Notation) -> Result<Expr, ParseError> {
+    let expression = Expression::from_str(input)?;
+
+    let expr = match notation {
+        Notation::Infix => Expr::try_from(&expression)?,
+        Notation::Postfix => Expr::try_from(&PostfixExpression(expression))?,
+        Notation::Prefix => Expr::try_from(&PrefixExpression(expression))?,
+    };
+
+    Ok(expr)
+}
+
+/// Work out which notation the tokens are written in. Notations are tried in turn and the first
+/// that parses is returned. This is never ambiguous: postfix ends with an operation and prefix
+/// starts with one, so neither can also be infix, and an expression valid in several notations
+/// is a lone number that means the same in each.
+pub fn detect_notation(expression: &Expression) -> Result<Notation, ParseError> {
+    let mut errors = Vec::<(Notation, FixExpressionError)>::new();
+
+    for notation in [Notation::Infix, Notation::Postfix, Notation::Prefix] {
+        let result = match notation {
+            Notation::Infix => Expr::try_from(expression),
+            Notation::Postfix => Expr::try_from(&PostfixExpression(expression.clone())),
+            Notation::Prefix => Expr::try_from(&PrefixExpression(expression.clone())),
+        };
+
+        match result {
+            Ok(_) => return Ok(notation),
+            Err(e) => errors.push((notation, e)),
+        }
+    }
+
+    Err(ParseError::Unrecognised(errors))
+}
+
+/// Lex `input`, detect whether it is infix, postfix or prefix, and return it as postfix so that
+/// it can be evaluated or verified.
+pub fn parse_any_notation(input: &str) -> Result<PostfixExpression, ParseError> {
+    let expression = Expression::from_str(input)?;
+    let notation = detect_notation(&expression)?;
+
+    Ok(parse(input, notation)?.to_postfix())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::rational::Ratio;
+    use test_case::test_case;
+
+    #[test_case("1 23  345 +  + ", Notation::Postfix; "example postfix expression")]
+    #[test_case("+ + 1 23  345 ", Notation::Prefix; "example prefix expression")]
+    #[test_case(" 1 + 23  + 345", Notation::Infix; "example infix expression")]
+    fn parse_each_notation(input: &str, notation: Notation) {
+        assert_eq!(
+            parse(input, notation).unwrap().evaluate(),
+            Ok(Ratio::from_integer(369))
+        );
+    }
+
+    #[test_case("(100 + 3) * 7", "100 3 + 7 *", "* + 100 3 7"; "nested expression")]
+    #[test_case("25 - 4 / 2", "25 4 2 / -", "- 25 / 4 2"; "precedence")]
+    fn parse_notations_agree(infix: &str, postfix: &str, prefix: &str) {
+        let expr = parse(infix, Notation::Infix).unwrap();
+        assert_eq!(parse(postfix, Notation::Postfix), Ok(expr.clone()));
+        assert_eq!(parse(prefix, Notation::Prefix), Ok(expr));
+    }
+
+    #[test_case("1 23 345 + +", Notation::Prefix, ParseError::Expression(FixExpressionError::StackUnderflow(4)); "postfix as prefix")]
+    #[test_case("+ + 1 23 345", Notation::Postfix, ParseError::Expression(FixExpressionError::StackUnderflow(0)); "prefix as postfix")]
+    #[test_case("1 + a", Notation::Infix, ParseError::Lex(LexError::InvalidCharacterAtIndex(4, 'a')); "lex error")]
+    fn parse_errors(input: &str, notation: Notation, expected: ParseError) {
+        assert_eq!(parse(input, notation), Err(expected));
+    }
+
+    #[test_case("(100 + 3) * 7", Notation::Infix; "infix")]
+    #[test_case("100 3 + 7 *", Notation::Postfix; "postfix")]
+    #[test_case("* + 100 3 7", Notation::Prefix; "prefix")]
+    #[test_case("7", Notation::Infix; "single number")]
+    #[test_case("(7)", Notation::Infix; "bracketed number")]
+    fn detect_notation_tests(input: &str, expected: Notation) {
+        assert_eq!(
+            detect_notation(&Expression::from_str(input).unwrap()),
+            Ok(expected)
+        );
+    }
+
+    #[test_case("(100 + 3) * 7"; "infix")]
+    #[test_case("100 3 + 7 *"; "postfix")]
+    #[test_case("* + 100 3 7"; "prefix")]
+    fn parse_any_notation_tests(input: &str) {
+        let postfix = parse_any_notation(input).unwrap();
+        assert_eq!(postfix.to_string(), "100 3 + 7 *");
+        assert_eq!(postfix.evaluate(), Ok(Ratio::from_integer(721)));
+    }
+
+    #[test]
+    fn parse_any_notation_unrecognised() {
+        assert_eq!(
+            parse_any_notation("1 + + 2"),
+            Err(ParseError::Unrecognised(vec![
+                (Notation::Infix, FixExpressionError::UnexpectedToken(2)),
+                (Notation::Postfix, FixExpressionError::StackUnderflow(1)),
+                (Notation::Prefix, FixExpressionError::StackUnderflow(2)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_any_notation_empty() {
+        assert_eq!(
+            parse_any_notation("  "),
+            Err(ParseError::Unrecognised(vec![
+                (Notation::Infix, FixExpressionError::EmptyExpression),
+                (Notation::Postfix, FixExpressionError::EmptyExpression),
+                (Notation::Prefix, FixExpressionError::EmptyExpression),
+            ]))
+        );
+    }
+
+    #[test_case("infix", Notation::Infix)]
+    #[test_case("RPN", Notation::Postfix)]
+    #[test_case("polish", Notation::Prefix)]
+    fn notation_from_str(input: &str, expected: Notation) {
+        assert_eq!(Notation::from_str(input), Ok(expected));
+    }
+}
@@ -0,0 +1,359 @@
+use super::ast::{operands_need_parentheses, Expr, Visitor};
+use super::expr::{FixExpressionError, Operation, PostfixExpression, Style, Token, TokenKind};
+use super::solver::{SearchOutcome, SearchTree};
+
+/// Typeset `expr` as LaTeX maths, e.g. `\left( 100 + 3 \right) \times 7`. Parentheses are only
+/// added where the infix form needs them, and exponents are grouped by the superscript instead.
+pub fn to_latex(expr: &PostfixExpression) -> Result<String, FixExpressionError> {
+    Ok(Expr::try_from(expr)?.accept(&mut Latex))
+}
+
+/// Typeset `expr` as a MathML `<math>` element, with the same parentheses as [`to_latex`].
+pub fn to_mathml(expr: &PostfixExpression) -> Result<String, FixExpressionError> {
+    let body = Expr::try_from(expr)?.accept(&mut MathMl);
+
+    Ok(format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>{}</mrow></math>",
+        body
+    ))
+}
+
+/// Draw `expr` as a tree with each operation above its operands, e.g.
+///
+/// ```text
+///     *
+///   ┌─┴─┐
+///   +   7
+///  ┌┴─┐
+/// 100 3
+/// ```
+pub fn to_tree(expr: &PostfixExpression, style: Style) -> Result<String, FixExpressionError> {
+    let block = Expr::try_from(expr)?.accept(&mut Tree { style });
+    let lines = block
+        .lines
+        .iter()
+        .map(|line| line.iter().collect::<String>().trim_end().to_string())
+        .collect::<Vec<String>>();
+
+    Ok(lines.join("\n"))
+}
+
+/// Export the expression tree of `expr` as a Graphviz DOT digraph, with operations pointing to
+/// their left then right operands.
+pub fn to_dot(expr: &PostfixExpression) -> Result<String, FixExpressionError> {
+    let mut dot = Dot {
+        lines: Vec::new(),
+        nodes: 0,
+    };
+    Expr::try_from(expr)?.accept(&mut dot);
+
+    Ok(format!("digraph solution {{\n{}}}\n", dot.lines.concat()))
+}
+
+/// Export a tree recorded by [`crate::solver::trace_search`] as a Graphviz DOT digraph. Each
+/// node is labelled with its partial expression and each edge with the token that was added.
+/// Solutions are filled and pruned branches are dashed.
+pub fn search_tree_to_dot(tree: &SearchTree) -> String {
+    let mut dot = String::from("digraph search {\n");
+
+    for (i, node) in tree.nodes.iter().enumerate() {
+        let style = match node.outcome {
+            SearchOutcome::Explored => "",
+            SearchOutcome::Solution => ", style=filled, fillcolor=palegreen",
+            SearchOutcome::Pruned => ", style=dashed, color=gray",
+        };
+        dot.push_str(&format!(
+            "    n{} [label=\"{}\"{}];\n",
+            i, node.expression, style
+        ));
+
+        if let (Some(parent), Some(token)) = (node.parent, node.expression.0.last()) {
+            dot.push_str(&format!(
+                "    n{} -> n{} [label=\"{}\"];\n",
+                parent, i, token
+            ));
+        }
+    }
+    if tree.truncated {
+        dot.push_str("    truncated [label=\"node limit reached\", shape=note];\n");
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+struct Dot {
+    lines: Vec<String>,
+    nodes: usize,
+}
+
+impl Dot {
+    fn node(&mut self, label: String) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        self.lines
+            .push(format!("    n{} [label=\"{}\"];\n", id, label));
+        id
+    }
+}
+
+impl Visitor<usize> for Dot {
+    fn visit_num(&mut self, n: usize) -> usize {
+        self.node(n.to_string())
+    }
+
+    fn visit_bin_op(&mut self, op: Operation, lhs: &Expr, rhs: &Expr) -> usize {
+        let id = self.node(op.to_string());
+        for operand in [lhs, rhs] {
+            let child = operand.accept(self);
+            self.lines.push(format!("    n{} -> n{};\n", id, child));
+        }
+        id
+    }
+}
+
+/// A rectangle of characters holding a subtree, with the column its root is drawn in.
+struct Block {
+    lines: Vec<Vec<char>>,
+    width: usize,
+    root: usize,
+}
+
+impl Block {
+    fn label(label: String) -> Block {
+        let chars = label.chars().collect::<Vec<char>>();
+        Block {
+            width: chars.len(),
+            root: (chars.len() - 1) / 2,
+            lines: vec![chars],
+        }
+    }
+}
+
+struct Tree {
+    style: Style,
+}
+
+impl Visitor<Block> for Tree {
+    fn visit_num(&mut self, n: usize) -> Block {
+        Block::label(Token::from(TokenKind::Number(n)).to_string_with(self.style))
+    }
+
+    fn visit_bin_op(&mut self, op: Operation, lhs: &Expr, rhs: &Expr) -> Block {
+        let lhs = lhs.accept(self);
+        let rhs = rhs.accept(self);
+
+        // Leave a one column gap between the subtrees, so the root always falls strictly
+        // between the two children
+        let rhs_offset = lhs.width + 1;
+        let width = rhs_offset + rhs.width;
+        let lhs_root = lhs.root;
+        let rhs_root = rhs_offset + rhs.root;
+        let root = (lhs_root + rhs_root) / 2;
+
+        let mut label = vec![' '; width];
+        label[root] = Token::from(TokenKind::Operation(op))
+            .to_string_with(self.style)
+            .chars()
+            .next()
+            .unwrap();
+
+        let mut connector = vec![' '; width];
+        for c in connector.iter_mut().take(rhs_root).skip(lhs_root + 1) {
+            *c = '─';
+        }
+        connector[lhs_root] = '┌';
+        connector[root] = '┴';
+        connector[rhs_root] = '┐';
+
+        let mut lines = vec![label, connector];
+        for i in 0..lhs.lines.len().max(rhs.lines.len()) {
+            let mut line = vec![' '; width];
+            if let Some(l) = lhs.lines.get(i) {
+                line[..l.len()].copy_from_slice(l);
+            }
+            if let Some(r) = rhs.lines.get(i) {
+                line[rhs_offset..rhs_offset + r.len()].copy_from_slice(r);
+            }
+            lines.push(line);
+        }
+
+        Block { lines, width, root }
+    }
+}
+
+struct Latex;
+
+impl Latex {
+    fn operand(&mut self, expr: &Expr, bracketed: bool) -> String {
+        let rendered = expr.accept(self);
+        if bracketed {
+            format!("\\left( {} \\right)", rendered)
+        } else {
+            rendered
+        }
+    }
+}
+
+impl Visitor<String> for Latex {
+    fn visit_num(&mut self, n: usize) -> String {
+        n.to_string()
+    }
+
+    fn visit_bin_op(&mut self, op: Operation, lhs: &Expr, rhs: &Expr) -> String {
+        let (lhs_parens, rhs_parens) = operands_need_parentheses(op, lhs, rhs);
+        let lhs = self.operand(lhs, lhs_parens);
+
+        let symbol = match op {
+            Operation::Add => "+",
+            Operation::Subtract => "-",
+            Operation::Multiply => "\\times",
+            Operation::Divide => "\\div",
+            Operation::Modulo => "\\bmod",
+            Operation::Concatenate => "\\mathbin{|}",
+            Operation::Power => return format!("{}^{{{}}}", lhs, rhs.accept(self)),
+        };
+
+        format!("{} {} {}", lhs, symbol, self.operand(rhs, rhs_parens))
+    }
+}
+
+struct MathMl;
+
+impl MathMl {
+    fn operand(&mut self, expr: &Expr, bracketed: bool) -> String {
+        let rendered = expr.accept(self);
+        if bracketed {
+            format!("<mo>(</mo>{}<mo>)</mo>", rendered)
+        } else {
+            rendered
+        }
+    }
+}
+
+impl Visitor<String> for MathMl {
+    fn visit_num(&mut self, n: usize) -> String {
+        format!("<mn>{}</mn>", n)
+    }
+
+    fn visit_bin_op(&mut self, op: Operation, lhs: &Expr, rhs: &Expr) -> String {
+        let (lhs_parens, rhs_parens) = operands_need_parentheses(op, lhs, rhs);
+        let lhs = self.operand(lhs, lhs_parens);
+
+        let symbol = match op {
+            Operation::Add => "+",
+            Operation::Subtract => "−",
+            Operation::Multiply => "×",
+            Operation::Divide => "÷",
+            Operation::Modulo => "mod",
+            Operation::Concatenate => "|",
+            Operation::Power => {
+                return format!(
+                    "<msup><mrow>{}</mrow><mrow>{}</mrow></msup>",
+                    lhs,
+                    rhs.accept(self)
+                )
+            }
+        };
+
+        format!(
+            "{}<mo>{}</mo>{}",
+            lhs,
+            symbol,
+            self.operand(rhs, rhs_parens)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{Expression, Radix, Symbols};
+    use crate::round::NumbersRound;
+    use crate::solver::{trace_search, SolverOptions};
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn postfix(input: &str) -> PostfixExpression {
+        PostfixExpression(Expression::from_str(input).unwrap())
+    }
+
+    #[test_case("100 3 + 7 *", "\\left( 100 + 3 \\right) \\times 7"; "bracketed sum")]
+    #[test_case("25 4 2 / -", "25 - 4 \\div 2"; "no parentheses needed")]
+    #[test_case("10 4 3 - -", "10 - \\left( 4 - 3 \\right)"; "right operand of subtraction")]
+    #[test_case("2 3 1 + ^", "2^{3 + 1}"; "exponent grouped by superscript")]
+    #[test_case("2 3 ^ 2 ^", "\\left( 2^{3} \\right)^{2}"; "power of a power")]
+    #[test_case("1 2 | 7 %", "1 \\mathbin{|} 2 \\bmod 7"; "concatenation and modulo")]
+    fn to_latex_tests(input: &str, expected: &str) {
+        assert_eq!(to_latex(&postfix(input)), Ok(expected.to_string()));
+    }
+
+    #[test_case("100 3 + 7 *", "<mo>(</mo><mn>100</mn><mo>+</mo><mn>3</mn><mo>)</mo><mo>×</mo><mn>7</mn>"; "bracketed sum")]
+    #[test_case("8 2 / 1 -", "<mn>8</mn><mo>÷</mo><mn>2</mn><mo>−</mo><mn>1</mn>"; "no parentheses needed")]
+    #[test_case("2 3 1 + ^", "<msup><mrow><mn>2</mn></mrow><mrow><mn>3</mn><mo>+</mo><mn>1</mn></mrow></msup>"; "power")]
+    fn to_mathml_tests(input: &str, expected: &str) {
+        assert_eq!(
+            to_mathml(&postfix(input)),
+            Ok(format!(
+                "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>{}</mrow></math>",
+                expected
+            ))
+        );
+    }
+
+    #[test_case("100 3 + 7 *", Style::default(), "    *\n  ┌─┴─┐\n  +   7\n ┌┴─┐\n100 3"; "nested operation")]
+    #[test_case("25", Style::default(), "25"; "single number")]
+    #[test_case("6 2 4 + /", Style::default(), " /\n┌┴─┐\n6  +\n  ┌┴┐\n  2 4"; "right operand")]
+    #[test_case("5 3 *", Style { symbols: Symbols::Typographic, radix: Radix::Binary }, "    ×\n  ┌─┴──┐\n0b101 0b11"; "styled")]
+    fn to_tree_tests(input: &str, style: Style, expected: &str) {
+        assert_eq!(to_tree(&postfix(input), style), Ok(expected.to_string()));
+    }
+
+    #[test]
+    fn to_dot_tests() {
+        assert_eq!(
+            to_dot(&postfix("100 3 + 7 *")),
+            Ok([
+                "digraph solution {",
+                "    n0 [label=\"*\"];",
+                "    n1 [label=\"+\"];",
+                "    n2 [label=\"100\"];",
+                "    n1 -> n2;",
+                "    n3 [label=\"3\"];",
+                "    n1 -> n3;",
+                "    n0 -> n1;",
+                "    n4 [label=\"7\"];",
+                "    n0 -> n4;",
+                "}\n",
+            ]
+            .join("\n"))
+        );
+    }
+
+    #[test]
+    fn search_tree_to_dot_tests() {
+        let tree = trace_search(
+            NumbersRound {
+                numbers: vec![1, 2],
+                target: 3,
+            },
+            &SolverOptions::default(),
+            true,
+            100,
+        );
+        let dot = search_tree_to_dot(&tree);
+
+        assert!(dot.starts_with("digraph search {\n    n0 [label=\"\"];\n"));
+        assert!(dot.contains("[label=\"2 1 +\", style=filled, fillcolor=palegreen];"));
+        assert!(dot.contains("[label=\"1 2 *\", style=dashed, color=gray];"));
+        assert!(!dot.contains("node limit reached"));
+    }
+
+    #[test]
+    fn invalid_expression() {
+        assert_eq!(
+            to_latex(&postfix("1 +")),
+            Err(FixExpressionError::StackUnderflow(1))
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::ast::Expr;
+use super::expr::PostfixExpression;
+use super::round::NumbersRound;
+use super::solver::SearchResult;
+
+/// A summary of solving a round, with a stable shape for scripts to consume as JSON.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Report {
+    pub numbers: Vec<usize>,
+    pub target: usize,
+    pub solutions: Vec<SolutionReport>,
+    /// The closest value reached, which equals the target if the round was solved.
+    pub closest: Option<SolutionReport>,
+    pub seconds: f64,
+    pub stats: SearchStats,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SolutionReport {
+    pub value: isize,
+    pub postfix: String,
+    pub infix: String,
+    /// Each operation in the order it is carried out, e.g. `100 + 3 = 103`.
+    pub steps: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchStats {
+    pub nodes_visited: usize,
+    pub solutions_found: usize,
+    pub timed_out: bool,
+}
+
+impl Report {
+    pub fn new(numbers_round: &NumbersRound, result: &SearchResult, duration: Duration) -> Report {
+        Report {
+            numbers: numbers_round.numbers.clone(),
+            target: numbers_round.target,
+            solutions: result
+                .solutions
+                .iter()
+                .filter_map(SolutionReport::new)
+                .collect(),
+            closest: result.closest.as_ref().and_then(SolutionReport::new),
+            seconds: duration.as_secs_f64(),
+            stats: SearchStats {
+                nodes_visited: result.nodes_visited,
+                solutions_found: result.solutions.len(),
+                timed_out: result.timed_out,
+            },
+        }
+    }
+}
+
+impl SolutionReport {
+    /// Describe `expression`, or return `None` if it is invalid or its value is not an integer.
+    pub fn new(expression: &PostfixExpression) -> Option<SolutionReport> {
+        let value = expression.evaluate().ok().filter(|v| v.is_integer())?;
+
+        Some(SolutionReport {
+            value: value.to_integer(),
+            postfix: expression.to_string(),
+            infix: Expr::try_from(expression).ok()?.to_string(),
+            steps: expression
+                .steps()
+                .ok()?
+                .iter()
+                .map(|step| step.to_string())
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::Expression;
+    use crate::solver::{search, SolverOptions};
+    use std::str::FromStr;
+
+    #[test]
+    fn solution_report() {
+        let expression = PostfixExpression(Expression::from_str("100 3 + 7 *").unwrap());
+
+        assert_eq!(
+            SolutionReport::new(&expression),
+            Some(SolutionReport {
+                value: 721,
+                postfix: "100 3 + 7 *".to_string(),
+                infix: "( 100 + 3 ) * 7".to_string(),
+                steps: vec!["100 + 3 = 103".to_string(), "103 * 7 = 721".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn unsolvable_report() {
+        let round = NumbersRound {
+            numbers: vec![1, 2],
+            target: 831,
+        };
+        let result = search(round.clone(), &SolverOptions::default(), true);
+        let report = Report::new(&round, &result, Duration::from_millis(250));
+
+        assert!(report.solutions.is_empty());
+        assert_eq!(report.closest.unwrap().value, 3);
+        assert_eq!(report.seconds, 0.25);
+        assert_eq!(report.stats.solutions_found, 0);
+        assert_eq!(report.stats.nodes_visited, result.nodes_visited);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn report_json_schema() {
+        let round = NumbersRound {
+            numbers: vec![2, 1],
+            target: 3,
+        };
+        let result = SearchResult {
+            solutions: vec![PostfixExpression(Expression::from_str("2 1 +").unwrap())],
+            closest: Some(PostfixExpression(Expression::from_str("2 1 +").unwrap())),
+            nodes_visited: 4,
+            timed_out: false,
+        };
+        let solution = serde_json::json!({
+            "value": 3,
+            "postfix": "2 1 +",
+            "infix": "2 + 1",
+            "steps": ["2 + 1 = 3"],
+        });
+
+        assert_eq!(
+            serde_json::to_value(Report::new(&round, &result, Duration::from_secs(1))).unwrap(),
+            serde_json::json!({
+                "numbers": [2, 1],
+                "target": 3,
+                "solutions": [solution],
+                "closest": solution,
+                "seconds": 1.0,
+                "stats": {"nodes_visited": 4, "solutions_found": 1, "timed_out": false},
+            })
+        );
+    }
+}
@@ -1,51 +1,401 @@
-use rand::seq::SliceRandom;
-use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::deck::TileDeck;
+use super::expr::Radix;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NumbersRound {
     pub numbers: Vec<usize>,
     pub target: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum NumbersRoundError {
     TooFewSmalls,
     TooManySmalls,
+    /// The rules' range of targets is empty.
+    NoTargets,
+}
+
+impl fmt::Display for NumbersRoundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumbersRoundError::TooFewSmalls => write!(f, "too few small tiles"),
+            NumbersRoundError::TooManySmalls => write!(f, "too many small tiles"),
+            NumbersRoundError::NoTargets => write!(f, "the rules allow no targets"),
+        }
+    }
+}
+
+impl std::error::Error for NumbersRoundError {}
+
+/// Why a round breaks a set of [`RoundRules`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum RoundRulesError {
+    WrongNumberOfTiles {
+        expected: usize,
+        actual: usize,
+    },
+    /// The tile is not in either pool, or appears more often than the pools allow.
+    UnavailableTile(usize),
+    /// The round uses a number of large tiles the rules do not allow.
+    BigsNotAllowed(usize),
+    TargetOutOfRange(usize),
+}
+
+impl fmt::Display for RoundRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoundRulesError::WrongNumberOfTiles { expected, actual } => {
+                write!(f, "expected {} tiles but found {}", expected, actual)
+            }
+            RoundRulesError::UnavailableTile(tile) => {
+                write!(f, "{} is not an available tile", tile)
+            }
+            RoundRulesError::BigsNotAllowed(n_big) => {
+                write!(f, "{} large tiles are not allowed", n_big)
+            }
+            RoundRulesError::TargetOutOfRange(target) => {
+                write!(f, "target {} is out of range", target)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoundRulesError {}
+
+/// The tiles and targets a numbers round is generated from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RoundRules {
+    /// Every small tile, repeated as often as it appears in the pool.
+    pub smalls: Vec<usize>,
+    pub bigs: Vec<usize>,
+    /// Number of tiles dealt in a round.
+    pub tiles: usize,
+    pub targets: RangeInclusive<usize>,
+    /// Numbers of large tiles a contestant may ask for.
+    pub allowed_bigs: RangeInclusive<usize>,
+}
+
+impl Default for RoundRules {
+    fn default() -> Self {
+        RoundRules::classic()
+    }
+}
+
+impl RoundRules {
+    /// The rules of the show: two each of 1 to 10, one each of 25, 50, 75 and 100, six tiles
+    /// with up to four large, and a target from 100 to 999.
+    pub fn classic() -> RoundRules {
+        RoundRules {
+            smalls: (1..=10).cycle().take(20).collect(),
+            bigs: vec![25, 50, 75, 100],
+            tiles: 6,
+            targets: 100..=999,
+            allowed_bigs: 0..=4,
+        }
+    }
+
+    /// The show's tiles with any target from 1 to 999, for easier rounds.
+    pub fn practice() -> RoundRules {
+        RoundRules {
+            targets: 1..=999,
+            ..RoundRules::classic()
+        }
+    }
+
+    /// The number of large tiles to deal alongside `n_small` small ones.
+    pub fn bigs_for(&self, n_small: usize) -> Result<usize, NumbersRoundError> {
+        if self.targets.is_empty() {
+            return Err(NumbersRoundError::NoTargets);
+        }
+        let n_big = self
+            .tiles
+            .checked_sub(n_small)
+            .ok_or(NumbersRoundError::TooManySmalls)?;
+
+        if n_big > *self.allowed_bigs.end() || n_big > self.bigs.len() {
+            Err(NumbersRoundError::TooFewSmalls)
+        } else if n_big < *self.allowed_bigs.start() || n_small > self.smalls.len() {
+            Err(NumbersRoundError::TooManySmalls)
+        } else {
+            Ok(n_big)
+        }
+    }
+
+    /// Check that `numbers_round` could have been dealt under these rules.
+    pub fn validate(&self, numbers_round: &NumbersRound) -> Result<(), RoundRulesError> {
+        if numbers_round.numbers.len() != self.tiles {
+            return Err(RoundRulesError::WrongNumberOfTiles {
+                expected: self.tiles,
+                actual: numbers_round.numbers.len(),
+            });
+        }
+
+        // A value in both pools may have been dealt from either, so work out how many large
+        // tiles the round could have used rather than assigning each tile to a pool
+        let smalls = count_tiles(&self.smalls);
+        let bigs = count_tiles(&self.bigs);
+        let wanted = count_tiles(&numbers_round.numbers);
+        let available =
+            |pool: &BTreeMap<usize, usize>, tile: &usize| pool.get(tile).copied().unwrap_or(0);
+
+        if let Some(tile) = numbers_round
+            .numbers
+            .iter()
+            .find(|tile| wanted[tile] > available(&smalls, tile) + available(&bigs, tile))
+        {
+            return Err(RoundRulesError::UnavailableTile(*tile));
+        }
+
+        let fewest_bigs = wanted
+            .iter()
+            .map(|(tile, n)| n.saturating_sub(available(&smalls, tile)))
+            .sum::<usize>();
+        let most_bigs = wanted
+            .iter()
+            .map(|(tile, n)| (*n).min(available(&bigs, tile)))
+            .sum::<usize>();
+        if fewest_bigs > *self.allowed_bigs.end() {
+            return Err(RoundRulesError::BigsNotAllowed(fewest_bigs));
+        }
+        if most_bigs < *self.allowed_bigs.start() {
+            return Err(RoundRulesError::BigsNotAllowed(most_bigs));
+        }
+        if !self.targets.contains(&numbers_round.target) {
+            return Err(RoundRulesError::TargetOutOfRange(numbers_round.target));
+        }
+
+        Ok(())
+    }
+}
+
+/// How many times each tile appears in `tiles`.
+fn count_tiles(tiles: &[usize]) -> BTreeMap<usize, usize> {
+    let mut counts = BTreeMap::new();
+    for tile in tiles {
+        *counts.entry(*tile).or_insert(0) += 1;
+    }
+    counts
 }
 
 impl NumbersRound {
+    /// Deal a round of the show with `n_small` small tiles.
     pub fn new(n_small: u8) -> Result<NumbersRound, NumbersRoundError> {
-        let mut rng = rand::thread_rng();
+        NumbersRound::with_rules(&RoundRules::classic(), n_small)
+    }
 
-        if n_small < 2 {
-            return Err(NumbersRoundError::TooFewSmalls);
-        } else if n_small > 6 {
-            return Err(NumbersRoundError::TooManySmalls);
-        }
+    /// Deal a round under `rules` with `n_small` small tiles.
+    pub fn with_rules(rules: &RoundRules, n_small: u8) -> Result<NumbersRound, NumbersRoundError> {
+        let n_small = usize::from(n_small);
+        let n_big = rules.bigs_for(n_small)?;
+        // `bigs_for` has checked that a full deck holds enough tiles and there are targets
+        Ok(TileDeck::from_rules(rules)
+            .deal_round(n_big, n_small)
+            .unwrap())
+    }
+}
+
+impl NumbersRound {
+    /// Render the round with every number written in `radix`, e.g.
+    /// `0b11001 0b110010 0b11 0b111 0b1 0b1001 -> 0b110011100`.
+    pub fn to_string_in(&self, radix: Radix) -> String {
+        let numbers = self
+            .numbers
+            .iter()
+            .map(|n| radix.format(*n))
+            .collect::<Vec<String>>();
+
+        format!("{} -> {}", numbers.join(" "), radix.format(self.target))
+    }
+}
 
-        let mut smalls: Vec<usize> = (1..=10).cycle().take(20).collect();
-        smalls.shuffle(&mut rng);
+impl fmt::Display for NumbersRound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_in(Radix::Decimal))
+    }
+}
 
-        let mut bigs: Vec<usize> = vec![25, 50, 75, 100];
-        bigs.shuffle(&mut rng);
+/// Read a round written as its tiles then target, either as `25 50 3 7 2 5 -> 615` (as
+/// displayed) or comma separated as `25,50,3,7,2,5,615`.
+impl FromStr for NumbersRound {
+    type Err = String;
 
-        let target = rng.gen_range(1..=999);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_number = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid number: {}", n.trim()))
+        };
 
-        let mut numbers = Vec::new();
+        let mut numbers = if let Some((tiles, target)) = s.split_once("->") {
+            let mut numbers = tiles
+                .split_whitespace()
+                .map(parse_number)
+                .collect::<Result<Vec<usize>, String>>()?;
+            numbers.push(parse_number(target)?);
+            numbers
+        } else if s.contains(',') {
+            s.split(',')
+                .map(parse_number)
+                .collect::<Result<Vec<usize>, String>>()?
+        } else {
+            return Err(format!("expected tiles and a target: {}", s.trim()));
+        };
 
-        for _ in 0..n_small {
-            numbers.push(smalls.pop().unwrap());
+        let target = numbers.pop().unwrap();
+        if numbers.is_empty() {
+            return Err(format!("round has no tiles: {}", s.trim()));
         }
 
-        if n_small < 6 {
-            for _ in 0..6 - n_small {
-                numbers.push(bigs.pop().unwrap());
-            }
+        Ok(NumbersRound { numbers, target })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(Radix::Binary, "0b11001 0b11 0b111 -> 0b1011111"; "binary")]
+    #[test_case(Radix::Octal, "0o31 0o3 0o7 -> 0o137"; "octal")]
+    #[test_case(Radix::Decimal, "25 3 7 -> 95"; "decimal")]
+    #[test_case(Radix::Hexadecimal, "0x19 0x3 0x7 -> 0x5F"; "hexadecimal")]
+    fn to_string_in_tests(radix: Radix, expected: &str) {
+        let round = NumbersRound {
+            numbers: vec![25, 3, 7],
+            target: 95,
+        };
+        assert_eq!(round.to_string_in(radix), expected);
+    }
+
+    #[test_case("25 50 3 7 2 5 -> 615"; "arrow")]
+    #[test_case("  25 50  3 7 2 5->615 "; "arrow with unusual spacing")]
+    #[test_case("25,50,3,7,2,5,615"; "comma separated")]
+    #[test_case("25, 50, 3, 7, 2, 5, 615"; "comma separated with spaces")]
+    fn from_str_tests(input: &str) {
+        assert_eq!(
+            NumbersRound::from_str(input),
+            Ok(NumbersRound {
+                numbers: vec![25, 50, 3, 7, 2, 5],
+                target: 615,
+            })
+        );
+    }
+
+    #[test_case("25 50 3"; "no target")]
+    #[test_case("-> 615"; "no tiles")]
+    #[test_case("25,fifty,615"; "invalid number")]
+    fn from_str_errors(input: &str) {
+        assert!(NumbersRound::from_str(input).is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let round = NumbersRound {
+            numbers: vec![100, 1, 8],
+            target: 801,
+        };
+        assert_eq!(NumbersRound::from_str(&round.to_string()), Ok(round));
+    }
+
+    #[test_case(4, Ok(2); "four smalls")]
+    #[test_case(1, Err(NumbersRoundError::TooFewSmalls); "five bigs")]
+    #[test_case(7, Err(NumbersRoundError::TooManySmalls); "more than six tiles")]
+    fn bigs_for_classic(n_small: usize, expected: Result<usize, NumbersRoundError>) {
+        assert_eq!(RoundRules::classic().bigs_for(n_small), expected);
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn bigs_for_no_targets() {
+        let rules = RoundRules {
+            targets: 999..=100,
+            ..RoundRules::classic()
+        };
+
+        assert_eq!(rules.bigs_for(4), Err(NumbersRoundError::NoTargets));
+        assert_eq!(
+            NumbersRound::with_rules(&rules, 4),
+            Err(NumbersRoundError::NoTargets)
+        );
+    }
+
+    #[test]
+    fn generate_with_rules() {
+        let rules = RoundRules {
+            smalls: vec![1, 2, 3],
+            bigs: vec![20, 40],
+            tiles: 4,
+            targets: 10..=20,
+            allowed_bigs: 1..=2,
+        };
+
+        for n_small in 2..=3 {
+            let numbers_round = NumbersRound::with_rules(&rules, n_small).unwrap();
+            assert_eq!(rules.validate(&numbers_round), Ok(()));
         }
+        assert_eq!(
+            NumbersRound::with_rules(&rules, 4).unwrap_err(),
+            NumbersRoundError::TooManySmalls
+        );
+        assert_eq!(
+            RoundRules::classic().validate(&NumbersRound::new(4).unwrap()),
+            Ok(())
+        );
+    }
+
+    #[test_case("25 50 3 7 2 5 -> 615", Ok(()); "valid")]
+    #[test_case("25 50 3 7 2 -> 615", Err(RoundRulesError::WrongNumberOfTiles { expected: 6, actual: 5 }); "five tiles")]
+    #[test_case("25 25 3 7 2 5 -> 615", Err(RoundRulesError::UnavailableTile(25)); "repeated big")]
+    #[test_case("3 3 3 7 2 5 -> 615", Err(RoundRulesError::UnavailableTile(3)); "three of a small")]
+    #[test_case("1 2 3 4 5 6 -> 61", Err(RoundRulesError::TargetOutOfRange(61)); "target below 100")]
+    fn validate_classic(round: &str, expected: Result<(), RoundRulesError>) {
+        let numbers_round = NumbersRound::from_str(round).unwrap();
+        assert_eq!(RoundRules::classic().validate(&numbers_round), expected);
+    }
+
+    #[test_case("25 1 2 -> 20", 1..=1, Ok(()); "shared value dealt as large")]
+    #[test_case("25 25 2 -> 20", 1..=1, Ok(()); "shared value from both pools")]
+    #[test_case("25 25 25 -> 20", 0..=2, Err(RoundRulesError::UnavailableTile(25)); "shared value too often")]
+    #[test_case("25 50 2 -> 20", 0..=0, Err(RoundRulesError::BigsNotAllowed(1)); "too many bigs")]
+    #[test_case("1 2 3 -> 20", 1..=1, Err(RoundRulesError::BigsNotAllowed(0)); "too few bigs")]
+    fn validate_shared_values(
+        round: &str,
+        allowed_bigs: RangeInclusive<usize>,
+        expected: Result<(), RoundRulesError>,
+    ) {
+        let rules = RoundRules {
+            smalls: vec![1, 2, 3, 25],
+            bigs: vec![25, 50],
+            tiles: 3,
+            targets: 10..=30,
+            allowed_bigs,
+        };
+        let numbers_round = NumbersRound::from_str(round).unwrap();
+
+        assert_eq!(rules.validate(&numbers_round), expected);
+    }
+
+    #[test]
+    fn validate_allowed_bigs() {
+        let rules = RoundRules {
+            allowed_bigs: 1..=2,
+            ..RoundRules::classic()
+        };
+        let numbers_round = NumbersRound::from_str("1 2 3 4 5 6 -> 615").unwrap();
 
-        Ok(NumbersRound {
-            numbers: numbers,
-            target,
-        })
+        assert_eq!(
+            rules.validate(&numbers_round),
+            Err(RoundRulesError::BigsNotAllowed(0))
+        );
     }
 }
@@ -0,0 +1,142 @@
+//! Serde support for types that need more than a derive. See the README for the JSON schema.
+
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::expr::{Expression, Token};
+
+/// Expressions are written as both their tokens and their string form, so consumers can use
+/// whichever is convenient.
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Expression", 2)?;
+        state.serialize_field("tokens", &self.0)?;
+        state.serialize_field("string", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExpressionRepr {
+    String(String),
+    Object {
+        tokens: Option<Vec<Token>>,
+        string: Option<String>,
+    },
+}
+
+/// Expressions are read from a string, or from an object with `tokens`, `string` or both. If
+/// both are given they must agree.
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let lex = |s: &str| Expression::from_str(s).map_err(D::Error::custom);
+
+        match ExpressionRepr::deserialize(deserializer)? {
+            ExpressionRepr::String(s) => lex(&s),
+            ExpressionRepr::Object {
+                tokens: Some(tokens),
+                string: None,
+            } => Ok(Expression(tokens)),
+            ExpressionRepr::Object {
+                tokens: None,
+                string: Some(s),
+            } => lex(&s),
+            ExpressionRepr::Object {
+                tokens: Some(tokens),
+                string: Some(s),
+            } => {
+                let expression = Expression(tokens);
+                if lex(&s)? != expression {
+                    return Err(D::Error::custom(format!(
+                        "tokens do not match string \"{}\"",
+                        s
+                    )));
+                }
+                Ok(expression)
+            }
+            ExpressionRepr::Object {
+                tokens: None,
+                string: None,
+            } => Err(D::Error::custom("expression needs tokens or a string")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{Operation, Parenthesis, PostfixExpression, TokenKind};
+    use crate::round::NumbersRound;
+    use serde_json::json;
+    use test_case::test_case;
+
+    #[test]
+    fn serialize_round() {
+        let round = NumbersRound {
+            numbers: vec![25, 3, 7],
+            target: 95,
+        };
+        let value = json!({"numbers": [25, 3, 7], "target": 95});
+
+        assert_eq!(serde_json::to_value(&round).unwrap(), value);
+        assert_eq!(
+            serde_json::from_value::<NumbersRound>(value).unwrap(),
+            round
+        );
+    }
+
+    #[test_case(TokenKind::Number(25), json!({"number": 25}); "number")]
+    #[test_case(TokenKind::Operation(Operation::Multiply), json!({"operation": "multiply"}); "operation")]
+    #[test_case(TokenKind::Parenthesis(Parenthesis::Open), json!({"parenthesis": "open"}); "parenthesis")]
+    fn serialize_token(kind: TokenKind, value: serde_json::Value) {
+        let token = Token::from(kind);
+        assert_eq!(serde_json::to_value(token).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Token>(value).unwrap(), token);
+    }
+
+    #[test]
+    fn serialize_postfix_expression() {
+        let expression = PostfixExpression(Expression::from_str("25 3 +").unwrap());
+
+        assert_eq!(
+            serde_json::to_value(&expression).unwrap(),
+            json!({
+                "tokens": [{"number": 25}, {"number": 3}, {"operation": "add"}],
+                "string": "25 3 +",
+            })
+        );
+    }
+
+    #[test_case(json!("(25 + 3) * 4"); "bare string")]
+    #[test_case(json!({"string": "( 25 + 3 ) × 4"}); "string field")]
+    #[test_case(json!({"tokens": [
+        {"parenthesis": "open"}, {"number": 25}, {"operation": "add"}, {"number": 3},
+        {"parenthesis": "close"}, {"operation": "multiply"}, {"number": 4},
+    ]}); "tokens field")]
+    #[test_case(json!({"tokens": [
+        {"parenthesis": "open"}, {"number": 25}, {"operation": "add"}, {"number": 3},
+        {"parenthesis": "close"}, {"operation": "multiply"}, {"number": 4},
+    ], "string": "( 25 + 3 ) * 4"}); "both fields")]
+    fn deserialize_expression(value: serde_json::Value) {
+        assert_eq!(
+            serde_json::from_value::<Expression>(value).unwrap(),
+            Expression::from_str("(25 + 3) * 4").unwrap()
+        );
+    }
+
+    #[test_case(json!({"tokens": [{"number": 1}], "string": "2"}), "tokens do not match string \"2\""; "mismatched fields")]
+    #[test_case(json!({}), "expression needs tokens or a string"; "no fields")]
+    #[test_case(json!("1 + a"), "invalid character 'a' at index 4"; "lex error")]
+    fn deserialize_expression_errors(value: serde_json::Value, message: &str) {
+        assert_eq!(
+            serde_json::from_value::<Expression>(value)
+                .unwrap_err()
+                .to_string(),
+            message
+        );
+    }
+}
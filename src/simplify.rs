@@ -0,0 +1,188 @@
+use num::rational::Ratio;
+
+use super::ast::Expr;
+use super::expr::Operation;
+
+/// The result of simplifying an expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Simplification {
+    pub expr: Expr,
+    /// Tiles used by the original expression that the simplified one no longer needs, in
+    /// ascending order.
+    pub unused_tiles: Vec<usize>,
+}
+
+/// Remove pointless steps such as `x * 1`, `(a + b) - b` or `x / x * y`.
+///
+/// Every node of the simplified expression is a node of the original, or is rebuilt from
+/// operands with the same values, so its value and intermediate values are ones the original
+/// already produced and it uses a subset of the original's tiles.
+pub fn simplify(expr: &Expr) -> Simplification {
+    let simplified = simplify_node(expr);
+
+    let mut unused_tiles = expr.tiles();
+    for tile in simplified.tiles() {
+        let i = unused_tiles.iter().position(|t| *t == tile).unwrap();
+        unused_tiles.swap_remove(i);
+    }
+    unused_tiles.sort();
+
+    Simplification {
+        expr: simplified,
+        unused_tiles,
+    }
+}
+
+fn simplify_node(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Num(n) => Expr::Num(*n),
+        Expr::BinOp { op, lhs, rhs } => {
+            let lhs = simplify_node(lhs);
+            let rhs = simplify_node(rhs);
+            // A reduction always returns an already simplified operand, so one pass suffices
+            match reduce(*op, &lhs, &rhs) {
+                Some(reduced) => reduced.clone(),
+                None => Expr::bin_op(*op, lhs, rhs),
+            }
+        }
+    }
+}
+
+fn has_value(expr: &Expr, n: isize) -> bool {
+    expr.evaluate() == Ok(Ratio::from_integer(n))
+}
+
+fn same_value(first: &Expr, second: &Expr) -> bool {
+    match (first.evaluate(), second.evaluate()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// If `lhs op rhs` can be replaced by one of its operands (or theirs), return that operand.
+fn reduce<'a>(op: Operation, lhs: &'a Expr, rhs: &'a Expr) -> Option<&'a Expr> {
+    match (op, lhs, rhs) {
+        (Operation::Add, _, _) if has_value(rhs, 0) => Some(lhs),
+        (Operation::Add, _, _) if has_value(lhs, 0) => Some(rhs),
+        // (a - b) + b
+        (
+            Operation::Add,
+            Expr::BinOp {
+                op: Operation::Subtract,
+                lhs: a,
+                rhs: b,
+            },
+            _,
+        ) if same_value(b, rhs) => Some(a),
+        // b + (a - b)
+        (
+            Operation::Add,
+            _,
+            Expr::BinOp {
+                op: Operation::Subtract,
+                lhs: a,
+                rhs: b,
+            },
+        ) if same_value(b, lhs) => Some(a),
+
+        (Operation::Subtract, _, _) if has_value(rhs, 0) => Some(lhs),
+        // (a + b) - b and (a + b) - a
+        (
+            Operation::Subtract,
+            Expr::BinOp {
+                op: Operation::Add,
+                lhs: a,
+                rhs: b,
+            },
+            _,
+        ) if same_value(b, rhs) => Some(a),
+        (
+            Operation::Subtract,
+            Expr::BinOp {
+                op: Operation::Add,
+                lhs: a,
+                rhs: b,
+            },
+            _,
+        ) if same_value(a, rhs) => Some(b),
+
+        (Operation::Multiply, _, _) if has_value(rhs, 1) => Some(lhs),
+        (Operation::Multiply, _, _) if has_value(lhs, 1) => Some(rhs),
+        // (a / b) * b
+        (
+            Operation::Multiply,
+            Expr::BinOp {
+                op: Operation::Divide,
+                lhs: a,
+                rhs: b,
+            },
+            _,
+        ) if same_value(b, rhs) => Some(a),
+        // b * (a / b)
+        (
+            Operation::Multiply,
+            _,
+            Expr::BinOp {
+                op: Operation::Divide,
+                lhs: a,
+                rhs: b,
+            },
+        ) if same_value(b, lhs) => Some(a),
+
+        (Operation::Divide, _, _) if has_value(rhs, 1) => Some(lhs),
+        // (a * b) / b and (a * b) / a
+        (
+            Operation::Divide,
+            Expr::BinOp {
+                op: Operation::Multiply,
+                lhs: a,
+                rhs: b,
+            },
+            _,
+        ) if same_value(b, rhs) => Some(a),
+        (
+            Operation::Divide,
+            Expr::BinOp {
+                op: Operation::Multiply,
+                lhs: a,
+                rhs: b,
+            },
+            _,
+        ) if same_value(a, rhs) => Some(b),
+
+        (Operation::Power, _, _) if has_value(rhs, 1) => Some(lhs),
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{Expression, PostfixExpression};
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn postfix(input: &str) -> Expr {
+        Expr::try_from(&PostfixExpression(Expression::from_str(input).unwrap())).unwrap()
+    }
+
+    #[test_case("25 1 *", "25", vec![1]; "multiply by one")]
+    #[test_case("1 25 *", "25", vec![1]; "multiply one by")]
+    #[test_case("100 3 4 + + 7 -", "100", vec![3, 4, 7]; "cancelling addition")]
+    #[test_case("6 6 / 25 *", "25", vec![6, 6]; "divide by self then multiply")]
+    #[test_case("50 2 * 2 /", "50", vec![2, 2]; "cancelling multiplication")]
+    #[test_case("8 3 - 3 +", "8", vec![3, 3]; "cancelling subtraction")]
+    #[test_case("7 3 3 - +", "7", vec![3, 3]; "add zero")]
+    #[test_case("10 5 4 - ^", "10", vec![4, 5]; "power of one")]
+    #[test_case("100 3 + 7 * 5 1 * 4 - /", "( 100 + 3 ) * 7", vec![1, 4, 5]; "nested identities")]
+    #[test_case("100 3 + 7 *", "( 100 + 3 ) * 7", vec![]; "nothing to simplify")]
+    fn simplify_tests(input: &str, expected: &str, unused_tiles: Vec<usize>) {
+        let original = postfix(input);
+        let simplification = simplify(&original);
+
+        assert_eq!(simplification.expr.to_string(), expected);
+        assert_eq!(simplification.unused_tiles, unused_tiles);
+        assert_eq!(simplification.expr.evaluate(), original.evaluate());
+    }
+}
@@ -1,20 +1,134 @@
-use super::expr::{Expression, Operation, PostfixExpression, Token};
+use super::expr::{Expression, Operation, PostfixExpression, Token, TokenKind};
 use super::round::NumbersRound;
 use num::rational::Ratio;
+use num::traits::Signed;
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+
+/// User-facing settings that control which expressions the solver may build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolverOptions {
+    pub allow_fractional_intermediate_values: bool,
+    /// Allow results below zero, as in "any integers" puzzle variants. The show forbids them.
+    pub allow_negative_intermediate_values: bool,
+    /// Operations the solver may use. Order determines the search order.
+    pub operations: Vec<Operation>,
+    /// Give up searching after this long, returning whatever has been found so far.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for SolverOptions {
+    fn default() -> SolverOptions {
+        SolverOptions {
+            allow_fractional_intermediate_values: false,
+            allow_negative_intermediate_values: false,
+            operations: Operation::STANDARD.to_vec(),
+            timeout: None,
+        }
+    }
+}
+
+/// What happened at a node of the search tree.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SearchOutcome {
+    /// The solver went on to extend this partial expression.
+    Explored,
+    /// The expression evaluates to the target.
+    Solution,
+    /// The solver did not extend this partial expression, because its operation is not
+    /// permitted for the operands or the result is zero.
+    Pruned,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchNode {
+    /// The partial postfix expression built so far. Its last token labels the edge from the
+    /// parent.
+    pub expression: Expression,
+    /// Index of the parent node in [`SearchTree::nodes`], or `None` for the root.
+    pub parent: Option<usize>,
+    pub outcome: SearchOutcome,
+}
+
+/// The part of the search space visited by the solver, for debugging and visualising pruning.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchTree {
+    /// Visited nodes in the order the solver reached them. The root, an empty expression, is
+    /// always first.
+    pub nodes: Vec<SearchNode>,
+    /// Whether nodes were left out because the search visited more than the node limit.
+    pub truncated: bool,
+}
+
+#[derive(Debug)]
+struct Trace {
+    tree: SearchTree,
+    max_nodes: usize,
+    /// The node for the current state, or `None` if it was not recorded.
+    current: Option<usize>,
+}
+
+impl Trace {
+    /// Record a child of the current node, returning its index if there was room.
+    fn push(&mut self, expression: &Expression, outcome: SearchOutcome) -> Option<usize> {
+        let parent = self.current?;
+        if self.tree.nodes.len() >= self.max_nodes {
+            self.tree.truncated = true;
+            return None;
+        }
+
+        self.tree.nodes.push(SearchNode {
+            expression: expression.clone(),
+            parent: Some(parent),
+            outcome,
+        });
+        Some(self.tree.nodes.len() - 1)
+    }
+
+    fn mark(&mut self, outcome: SearchOutcome) {
+        if let Some(i) = self.current {
+            self.tree.nodes[i].outcome = outcome;
+        }
+    }
+}
 
 #[derive(Debug)]
 struct Config {
-    target: Ratio<usize>,
+    /// `None` if the target is too large to be represented, in which case it is unreachable.
+    target: Option<Ratio<isize>>,
     allow_fractional_intermediate_values: bool,
+    allow_negative_intermediate_values: bool,
+    operations: Vec<Operation>,
     stop_at_first_solution: bool,
+    deadline: Option<Instant>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct StackEntry {
+    value: Ratio<isize>,
+    /// Whether this entry is an unmodified tile rather than an intermediate result.
+    is_tile: bool,
 }
 
 #[derive(Debug)]
 struct State {
     expression: Expression,
-    stack: Vec<Ratio<usize>>,
+    stack: Vec<StackEntry>,
     remaining: Vec<usize>,
-    history: Vec<Ratio<usize>>,
+    history: Vec<StackEntry>,
+}
+
+/// Everything a search found, for callers that want more than the solutions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    pub solutions: Vec<PostfixExpression>,
+    /// An expression whose value is an integer as near to the target as any the search reached,
+    /// which is a solution if there are any.
+    pub closest: Option<PostfixExpression>,
+    /// Number of partial expressions the solver considered.
+    pub nodes_visited: usize,
+    /// Whether the search stopped early because it ran out of time.
+    pub timed_out: bool,
 }
 
 #[derive(Debug)]
@@ -22,96 +136,239 @@ struct Solver {
     config: Config,
     state: State,
     solutions: Vec<PostfixExpression>,
+    /// The closest complete expression with an integer value so far, and its distance from the
+    /// target.
+    closest: Option<(PostfixExpression, isize)>,
+    nodes_visited: usize,
+    timed_out: bool,
+    trace: Option<Trace>,
 }
 
 impl Solver {
     fn new(
         numbers_round: NumbersRound,
-        allow_fractional_intermediate_values: bool,
+        options: &SolverOptions,
         stop_at_first_solution: bool,
     ) -> Solver {
         let config = Config {
-            target: Ratio::<usize>::from_integer(numbers_round.target.try_into().unwrap()),
-            allow_fractional_intermediate_values: allow_fractional_intermediate_values,
-            stop_at_first_solution: stop_at_first_solution,
+            target: isize::try_from(numbers_round.target)
+                .ok()
+                .map(Ratio::<isize>::from_integer),
+            allow_fractional_intermediate_values: options.allow_fractional_intermediate_values,
+            allow_negative_intermediate_values: options.allow_negative_intermediate_values,
+            operations: options.operations.clone(),
+            stop_at_first_solution,
+            deadline: options.timeout.map(|timeout| Instant::now() + timeout),
         };
         let state = State {
             expression: Expression::new(),
-            stack: Vec::<Ratio<usize>>::new(),
+            stack: Vec::<StackEntry>::new(),
             remaining: numbers_round.numbers,
-            history: Vec::<Ratio<usize>>::new(),
+            history: Vec::<StackEntry>::new(),
         };
         Solver {
             state,
             config,
             solutions: Vec::<PostfixExpression>::new(),
+            closest: None,
+            nodes_visited: 0,
+            timed_out: false,
+            trace: None,
         }
     }
 
+    /// Record the search tree as the solver runs, keeping at most `max_nodes` nodes.
+    fn with_trace(mut self, max_nodes: usize) -> Solver {
+        let root = SearchNode {
+            expression: Expression::new(),
+            parent: None,
+            outcome: SearchOutcome::Explored,
+        };
+        self.trace = Some(Trace {
+            tree: SearchTree {
+                nodes: vec![root],
+                truncated: false,
+            },
+            max_nodes: max_nodes.max(1),
+            current: Some(0),
+        });
+        self
+    }
+
     fn get_options(&self) -> Vec<Token> {
         // Populate options to append to end of current partial solution
         let mut options = Vec::<Token>::new();
 
-        // Remaining numbers
+        // Remaining numbers, skipping any too large to be represented
         for r in self.state.remaining.iter() {
-            options.push(Token::Number(*r));
+            if isize::try_from(*r).is_ok() {
+                options.push(TokenKind::Number(*r).into());
+            }
         }
 
-        // All operations if at least two numbers on the stack
+        // Allowed operations if at least two numbers on the stack
         if self.state.stack.len() >= 2 {
-            let first_num = &self.state.stack[self.state.stack.len() - 2];
-            let second_num = self.state.stack.last().unwrap();
+            let first = &self.state.stack[self.state.stack.len() - 2];
+            let second = self.state.stack.last().unwrap();
 
-            if first_num >= second_num {
-                options.push(Token::Operation(Operation::Add));
-                options.push(Token::Operation(Operation::Subtract));
-                options.push(Token::Operation(Operation::Multiply));
+            for op in self.config.operations.iter() {
+                if self.apply(*op, first, second).is_some() {
+                    options.push(TokenKind::Operation(*op).into());
+                }
             }
+        }
 
-            if self.config.allow_fractional_intermediate_values
-                || (first_num >= second_num && *(first_num % second_num).numer() == 0)
-            {
-                options.push(Token::Operation(Operation::Divide));
+        options
+    }
+
+    /// Apply `op` to the top two stack values, returning `None` if the operation is not permitted
+    /// for these operands or its result overflows an `isize`, which prunes the branch.
+    fn apply(
+        &self,
+        op: Operation,
+        first: &StackEntry,
+        second: &StackEntry,
+    ) -> Option<Ratio<isize>> {
+        let first_num = &first.value;
+        let second_num = &second.value;
+
+        let permitted = match op {
+            // Commutative operations only need to be tried in one order
+            Operation::Add | Operation::Multiply => first_num >= second_num,
+            Operation::Subtract => {
+                self.config.allow_negative_intermediate_values || first_num >= second_num
+            }
+            Operation::Divide => {
+                self.config.allow_fractional_intermediate_values
+                    || compare_magnitudes(first_num, second_num).is_some_and(Ordering::is_ge)
             }
+            Operation::Power => true,
+            // Taking the remainder by a larger number leaves the first number unchanged
+            Operation::Modulo => {
+                compare_magnitudes(first_num, second_num).is_some_and(Ordering::is_gt)
+            }
+            // Only raw tiles may be concatenated, never intermediate results
+            Operation::Concatenate => first.is_tile && second.is_tile,
+        };
+        if !permitted {
+            return None;
         }
 
-        options
+        let result = op.checked_apply(first_num, second_num).ok()?;
+        if !self.config.allow_fractional_intermediate_values && !result.is_integer() {
+            return None;
+        }
+        if !self.config.allow_negative_intermediate_values && result.is_negative() {
+            return None;
+        }
+
+        Some(result)
     }
 
     fn find_solutions(&mut self) {
-        if self.state.stack.len() == 1 && *self.state.stack.first().unwrap() == self.config.target {
+        let Some(target) = self.config.target else {
+            return;
+        };
+        self.nodes_visited += 1;
+
+        // Reading the clock is slow relative to visiting a node, so only check it periodically
+        if let Some(deadline) = self.config.deadline {
+            if self.nodes_visited.is_multiple_of(1024) && Instant::now() >= deadline {
+                self.timed_out = true;
+            }
+        }
+        if self.timed_out {
+            return;
+        }
+
+        if self.state.stack.len() == 1 && self.state.stack[0].value.is_integer() {
+            // Values far from the target are skipped rather than overflowing
+            let distance = self.state.stack[0]
+                .value
+                .to_integer()
+                .checked_sub(target.to_integer())
+                .and_then(|d| d.checked_abs());
+            if let Some(distance) = distance {
+                let closer = match &self.closest {
+                    Some((_, best)) => distance < *best,
+                    None => true,
+                };
+                if closer {
+                    self.closest =
+                        Some((PostfixExpression(self.state.expression.clone()), distance));
+                }
+            }
+        }
+
+        if self.state.stack.len() == 1 && self.state.stack[0].value == target {
             self.solutions
                 .push(PostfixExpression(self.state.expression.clone()));
+            if let Some(trace) = self.trace.as_mut() {
+                trace.mark(SearchOutcome::Solution);
+            }
             if self.config.stop_at_first_solution {
                 return; // early exit for now
             }
         }
 
-        if self.state.remaining.len() == 0 && self.state.stack.len() == 1 {
+        if self.state.remaining.is_empty() && self.state.stack.len() == 1 {
             return;
         }
 
-        if self.state.stack.len() > 0 && *self.state.stack.last().unwrap().numer() == 0 {
+        if !self.state.stack.is_empty() && *self.state.stack.last().unwrap().value.numer() == 0 {
+            if let Some(trace) = self.trace.as_mut() {
+                trace.mark(SearchOutcome::Pruned);
+            }
             return;
         }
 
         let options = self.get_options();
+        if self.trace.is_some() {
+            self.trace_rejected_operations(&options);
+        }
         for token in options {
             self.compute_next(token);
+            let parent = self.trace.as_mut().map(|trace| {
+                let parent = trace.current;
+                trace.current = trace.push(&self.state.expression, SearchOutcome::Explored);
+                parent
+            });
             self.find_solutions();
+            if let (Some(trace), Some(parent)) = (self.trace.as_mut(), parent) {
+                trace.current = parent;
+            }
             self.revert();
-            if self.config.stop_at_first_solution && self.solutions.len() > 0 {
+            if (self.config.stop_at_first_solution && !self.solutions.is_empty()) || self.timed_out
+            {
                 break;
             }
         }
     }
 
+    /// Record operations that `get_options` ruled out for the top two stack values as pruned
+    /// children of the current node.
+    fn trace_rejected_operations(&mut self, options: &[Token]) {
+        if self.state.stack.len() < 2 {
+            return;
+        }
+
+        let trace = self.trace.as_mut().unwrap();
+        for op in self.config.operations.iter() {
+            if !options.contains(&Token::from(TokenKind::Operation(*op))) {
+                let mut expression = self.state.expression.clone();
+                expression.0.push(TokenKind::Operation(*op).into());
+                trace.push(&expression, SearchOutcome::Pruned);
+            }
+        }
+    }
+
     fn compute_next(&mut self, token: Token) {
-        match token {
-            Token::Number(n) => {
-                self.state
-                    .stack
-                    .push(Ratio::<usize>::from_integer((n).try_into().unwrap()));
+        match token.kind {
+            TokenKind::Number(n) => {
+                self.state.stack.push(StackEntry {
+                    value: Ratio::<isize>::from_integer(n.try_into().unwrap()),
+                    is_tile: true,
+                });
 
                 self.state.remaining = {
                     let mut new_vec = Vec::new();
@@ -126,24 +383,22 @@ impl Solver {
                     new_vec
                 };
             }
-            Token::Operation(op) => {
-                let last_num = self.state.stack.pop().unwrap();
-                let first_num = self.state.stack.pop().unwrap();
-
-                let result = match op {
-                    Operation::Add => first_num + last_num,
-                    Operation::Subtract => first_num - last_num,
-                    Operation::Multiply => first_num * last_num,
-                    Operation::Divide => first_num / last_num,
-                };
+            TokenKind::Operation(op) => {
+                let last = self.state.stack.pop().unwrap();
+                let first = self.state.stack.pop().unwrap();
+
+                let result = self.apply(op, &first, &last).unwrap();
 
-                self.state.stack.push(result);
+                self.state.stack.push(StackEntry {
+                    value: result,
+                    is_tile: false,
+                });
 
                 // History pushed in reverse order so they come off the stack later in correct order
-                self.state.history.push(last_num);
-                self.state.history.push(first_num);
+                self.state.history.push(last);
+                self.state.history.push(first);
             }
-            Token::Parenthesis(_) => panic!("Unexpected parenthesis token found."),
+            TokenKind::Parenthesis(_) => panic!("Unexpected parenthesis token found."),
         }
 
         self.state.expression.0.push(token);
@@ -153,38 +408,104 @@ impl Solver {
         self.state.stack.pop();
         let last_token = self.state.expression.0.pop().unwrap();
 
-        match last_token {
-            Token::Number(n) => {
+        match last_token.kind {
+            TokenKind::Number(n) => {
                 self.state.remaining.push(n);
             }
-            Token::Operation(_) => {
+            TokenKind::Operation(_) => {
                 self.state.stack.push(self.state.history.pop().unwrap());
                 self.state.stack.push(self.state.history.pop().unwrap());
             }
-            Token::Parenthesis(_) => panic!("Unexpected parenthesis token found."),
+            TokenKind::Parenthesis(_) => panic!("Unexpected parenthesis token found."),
         }
     }
 }
 
+/// Compare the absolute values of `first` and `second`, or `None` if either is too large to
+/// represent, as the absolute value of `isize::MIN` is.
+fn compare_magnitudes(first: &Ratio<isize>, second: &Ratio<isize>) -> Option<Ordering> {
+    let first = Ratio::new_raw(first.numer().checked_abs()?, *first.denom());
+    let second = Ratio::new_raw(second.numer().checked_abs()?, *second.denom());
+    Some(first.cmp(&second))
+}
+
 pub fn find_solution(
     numbers_round: NumbersRound,
     allow_fractional_intermediate_values: bool,
 ) -> Option<PostfixExpression> {
-    let mut solver = Solver::new(numbers_round, allow_fractional_intermediate_values, true);
-    solver.find_solutions();
-    solver.solutions.first().cloned()
+    find_solution_with_options(
+        numbers_round,
+        &SolverOptions {
+            allow_fractional_intermediate_values,
+            ..SolverOptions::default()
+        },
+    )
 }
 
 pub fn find_solutions(
     numbers_round: NumbersRound,
     allow_fractional_intermediate_values: bool,
 ) -> Vec<PostfixExpression> {
-    let mut solver = Solver::new(numbers_round, allow_fractional_intermediate_values, false);
+    find_solutions_with_options(
+        numbers_round,
+        &SolverOptions {
+            allow_fractional_intermediate_values,
+            ..SolverOptions::default()
+        },
+    )
+}
+
+pub fn find_solution_with_options(
+    numbers_round: NumbersRound,
+    options: &SolverOptions,
+) -> Option<PostfixExpression> {
+    search(numbers_round, options, true)
+        .solutions
+        .first()
+        .cloned()
+}
+
+pub fn find_solutions_with_options(
+    numbers_round: NumbersRound,
+    options: &SolverOptions,
+) -> Vec<PostfixExpression> {
+    search(numbers_round, options, false).solutions
+}
+
+/// Run the solver, stopping at the first solution if `stop_at_first_solution`, and report what
+/// it found along the way.
+pub fn search(
+    numbers_round: NumbersRound,
+    options: &SolverOptions,
+    stop_at_first_solution: bool,
+) -> SearchResult {
+    let mut solver = Solver::new(numbers_round, options, stop_at_first_solution);
     solver.find_solutions();
-    solver.solutions
+    SearchResult {
+        solutions: solver.solutions,
+        closest: solver.closest.map(|(expression, _)| expression),
+        nodes_visited: solver.nodes_visited,
+        timed_out: solver.timed_out,
+    }
+}
+
+/// Run the solver as [`find_solutions_with_options`] does, or as [`find_solution_with_options`]
+/// does if `stop_at_first_solution`, and return the tree of partial expressions it visited.
+/// Recording stops after `max_nodes` nodes, but the search itself runs to completion.
+pub fn trace_search(
+    numbers_round: NumbersRound,
+    options: &SolverOptions,
+    stop_at_first_solution: bool,
+    max_nodes: usize,
+) -> SearchTree {
+    let mut solver =
+        Solver::new(numbers_round, options, stop_at_first_solution).with_trace(max_nodes);
+    solver.find_solutions();
+    solver.trace.unwrap().tree
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_field_names, clippy::len_zero)]
 mod tests {
     use super::*;
     use test_case::test_case;
@@ -249,6 +570,130 @@ mod tests {
         );
     }
 
+    #[test_case(1000, vec![10, 3], Operation::ALL.to_vec(); "power")]
+    #[test_case(3, vec![7, 4], vec![Operation::Modulo]; "modulo only")]
+    #[test_case(78, vec![3, 2, 7, 6], vec![Operation::Add, Operation::Multiply]; "add and multiply only")]
+    #[test_case(12, vec![1, 2], vec![Operation::Concatenate]; "concatenation only")]
+    #[test_case(300, vec![1, 2, 25], vec![Operation::Add, Operation::Multiply, Operation::Concatenate]; "concatenation then multiply")]
+    fn find_solution_with_operations(
+        target: usize,
+        numbers: Vec<usize>,
+        operations: Vec<Operation>,
+    ) {
+        let solution = find_solution_with_options(
+            NumbersRound { numbers, target },
+            &SolverOptions {
+                operations: operations.clone(),
+                ..SolverOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(solution.0.uses_only(&operations));
+        assert_eq!(
+            solution.evaluate().unwrap(),
+            Ratio::<isize>::from_integer(target.try_into().unwrap())
+        );
+    }
+
+    #[test_case(3, vec![2, 5], vec![Operation::Add, Operation::Multiply]; "subtraction required")]
+    #[test_case(1000, vec![10, 3], Operation::STANDARD.to_vec(); "power required")]
+    #[test_case(127, vec![1, 2, 7], vec![Operation::Concatenate]; "intermediate results are not concatenated")]
+    fn impossible_with_operations(target: usize, numbers: Vec<usize>, operations: Vec<Operation>) {
+        let solution = find_solution_with_options(
+            NumbersRound { numbers, target },
+            &SolverOptions {
+                operations,
+                ..SolverOptions::default()
+            },
+        );
+
+        assert!(solution.is_none());
+    }
+
+    #[test_case(2, vec![3, 5, 4])]
+    #[test_case(96, vec![1, 3, 25, 75])]
+    fn find_solutions_with_negative_intermediates(target: usize, numbers: Vec<usize>) {
+        let round = NumbersRound { numbers, target };
+        let has_negative_step = |solution: &PostfixExpression| {
+            solution
+                .steps()
+                .unwrap()
+                .iter()
+                .any(|step| step.result < Ratio::from_integer(0))
+        };
+
+        let without_negatives = find_solutions(round.clone(), false);
+        assert!(!without_negatives.iter().any(has_negative_step));
+
+        let with_negatives = find_solutions_with_options(
+            round,
+            &SolverOptions {
+                allow_negative_intermediate_values: true,
+                ..SolverOptions::default()
+            },
+        );
+        assert!(with_negatives.iter().any(has_negative_step));
+        for solution in with_negatives {
+            assert_eq!(
+                solution.evaluate().unwrap(),
+                Ratio::<isize>::from_integer(target.try_into().unwrap())
+            );
+        }
+    }
+
+    #[test_case(7, vec![usize::MAX, 3, 4]; "tile too large to represent")]
+    #[test_case(3, vec![1 << 40, 1 << 40, 3]; "product overflows")]
+    #[test_case(1 << 62, vec![1 << 31, 1 << 31, 1 << 31]; "large target")]
+    fn find_solution_near_overflow(target: usize, numbers: Vec<usize>) {
+        let solution = find_solution_with_options(
+            NumbersRound { numbers, target },
+            &SolverOptions {
+                operations: Operation::ALL.to_vec(),
+                ..SolverOptions::default()
+            },
+        );
+
+        assert_eq!(
+            solution.unwrap().evaluate().unwrap(),
+            Ratio::<isize>::from_integer(target.try_into().unwrap())
+        );
+    }
+
+    #[test]
+    fn find_solution_with_negatives_near_overflow() {
+        let solution = find_solution_with_options(
+            NumbersRound {
+                numbers: vec![1, isize::MAX as usize, 2, 3],
+                target: 5,
+            },
+            &SolverOptions {
+                operations: Operation::ALL.to_vec(),
+                allow_negative_intermediate_values: true,
+                ..SolverOptions::default()
+            },
+        );
+
+        assert_eq!(
+            solution.unwrap().evaluate().unwrap(),
+            Ratio::from_integer(5)
+        );
+    }
+
+    #[test_case(usize::MAX, vec![usize::MAX, 1]; "target too large to represent")]
+    #[test_case(1 << 63, vec![1 << 32, 1 << 31]; "target reachable only with overflow")]
+    fn impossible_without_overflow(target: usize, numbers: Vec<usize>) {
+        let solution = find_solution_with_options(
+            NumbersRound { numbers, target },
+            &SolverOptions {
+                operations: Operation::ALL.to_vec(),
+                ..SolverOptions::default()
+            },
+        );
+
+        assert!(solution.is_none());
+    }
+
     #[test_case(30, vec![1, 2])]
     #[test_case(3000, vec![2, 3, 5, 10])]
     #[test_case(30000, vec![2, 3, 5, 8, 10])]
@@ -264,4 +709,106 @@ mod tests {
 
         assert!(solution.is_none(),);
     }
+
+    #[test]
+    fn trace_search_records_every_solution() {
+        let numbers_round = NumbersRound {
+            numbers: vec![6, 5, 1, 25],
+            target: 55,
+        };
+        let options = SolverOptions::default();
+        let tree = trace_search(numbers_round.clone(), &options, false, usize::MAX);
+        let solutions = find_solutions_with_options(numbers_round, &options);
+
+        assert!(!tree.truncated);
+        assert_eq!(
+            tree.nodes
+                .iter()
+                .filter(|n| n.outcome == SearchOutcome::Solution)
+                .map(|n| PostfixExpression(n.expression.clone()))
+                .collect::<Vec<PostfixExpression>>(),
+            solutions
+        );
+        for node in tree.nodes.iter().skip(1) {
+            let parent = &tree.nodes[node.parent.unwrap()].expression;
+            assert_eq!(
+                parent.0[..],
+                node.expression.0[..node.expression.0.len() - 1]
+            );
+        }
+    }
+
+    #[test]
+    fn trace_search_node_limit() {
+        let tree = trace_search(
+            NumbersRound {
+                numbers: vec![1, 10, 25, 50, 75, 100],
+                target: 813,
+            },
+            &SolverOptions::default(),
+            false,
+            50,
+        );
+
+        assert_eq!(tree.nodes.len(), 50);
+        assert!(tree.truncated);
+    }
+
+    #[test_case(615, vec![25, 3, 7, 2, 5, 4], 615; "solvable")]
+    #[test_case(831, vec![1, 2], 3; "unreachable")]
+    #[test_case(8, vec![2, 3], 6; "closest from below")]
+    fn search_closest(target: usize, numbers: Vec<usize>, closest: isize) {
+        let result = search(
+            NumbersRound { numbers, target },
+            &SolverOptions::default(),
+            true,
+        );
+
+        assert_eq!(
+            result.closest.unwrap().evaluate(),
+            Ok(Ratio::from_integer(closest))
+        );
+        assert!(result.nodes_visited > 0);
+    }
+
+    #[test]
+    fn search_closest_large_fraction() {
+        // 1 / 2^62 is representable, but its distance from the target is not
+        let result = search(
+            NumbersRound {
+                numbers: vec![1, 1 << 62],
+                target: 999,
+            },
+            &SolverOptions {
+                allow_fractional_intermediate_values: true,
+                allow_negative_intermediate_values: true,
+                ..SolverOptions::default()
+            },
+            false,
+        );
+
+        assert!(result.solutions.is_empty());
+        assert_eq!(
+            result.closest.unwrap().evaluate(),
+            Ok(Ratio::from_integer(1))
+        );
+    }
+
+    #[test]
+    fn search_timeout() {
+        let numbers_round = NumbersRound {
+            numbers: vec![1, 3, 5, 7, 9, 11],
+            target: 999,
+        };
+        let options = SolverOptions {
+            allow_fractional_intermediate_values: true,
+            allow_negative_intermediate_values: true,
+            operations: Operation::ALL.to_vec(),
+            timeout: Some(Duration::ZERO),
+        };
+        let result = search(numbers_round, &options, false);
+
+        assert!(result.timed_out);
+        assert_eq!(result.nodes_visited, 1024);
+    }
 }
@@ -0,0 +1,136 @@
+use super::expr::{FixExpressionError, Operation, PostfixExpression, TokenKind};
+use super::round::NumbersRound;
+use super::solver::SolverOptions;
+use num::rational::Ratio;
+use num::traits::Signed;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum VerifyError {
+    InvalidExpression(FixExpressionError),
+    UnavailableTile(usize),
+    DisallowedOperation(Operation),
+    NegativeIntermediateValue(Ratio<isize>),
+    FractionalIntermediateValue(Ratio<isize>),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::InvalidExpression(e) => write!(f, "{}", e),
+            VerifyError::UnavailableTile(n) => write!(f, "{} is not an available tile", n),
+            VerifyError::DisallowedOperation(op) => write!(f, "{} is not allowed", op),
+            VerifyError::NegativeIntermediateValue(v) => {
+                write!(f, "intermediate value {} is negative", v)
+            }
+            VerifyError::FractionalIntermediateValue(v) => {
+                write!(f, "intermediate value {} is not a whole number", v)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<FixExpressionError> for VerifyError {
+    fn from(error: FixExpressionError) -> VerifyError {
+        VerifyError::InvalidExpression(error)
+    }
+}
+
+/// Check that `solution` only uses tiles from `numbers_round` and obeys the same rules as the
+/// solver does under `options`, returning the value it evaluates to.
+pub fn verify(
+    numbers_round: &NumbersRound,
+    solution: &PostfixExpression,
+    options: &SolverOptions,
+) -> Result<Ratio<isize>, VerifyError> {
+    let mut remaining = numbers_round.numbers.clone();
+
+    for token in solution.0 .0.iter() {
+        match token.kind {
+            TokenKind::Number(n) => match remaining.iter().position(|r| *r == n) {
+                Some(i) => {
+                    remaining.swap_remove(i);
+                }
+                None => return Err(VerifyError::UnavailableTile(n)),
+            },
+            TokenKind::Operation(op) => {
+                if !options.operations.contains(&op) {
+                    return Err(VerifyError::DisallowedOperation(op));
+                }
+            }
+            TokenKind::Parenthesis(_) => {}
+        }
+    }
+
+    for step in solution.steps()? {
+        if !options.allow_negative_intermediate_values && step.result.is_negative() {
+            return Err(VerifyError::NegativeIntermediateValue(step.result));
+        }
+        if !options.allow_fractional_intermediate_values && !step.result.is_integer() {
+            return Err(VerifyError::FractionalIntermediateValue(step.result));
+        }
+    }
+
+    Ok(solution.evaluate()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::Expression;
+    use std::str::FromStr;
+    use test_case::test_case;
+
+    fn postfix(input: &str) -> PostfixExpression {
+        PostfixExpression(Expression::from_str(input).unwrap())
+    }
+
+    #[test_case("25 3 * 7 +", 82; "simple solution")]
+    #[test_case("2 2 *", 4; "repeated tile")]
+    #[test_case("7", 7; "single tile")]
+    fn verify_valid_solutions(input: &str, expected: isize) {
+        let round = NumbersRound {
+            numbers: vec![25, 3, 7, 2, 2, 100],
+            target: 82,
+        };
+        assert_eq!(
+            verify(&round, &postfix(input), &SolverOptions::default()),
+            Ok(Ratio::from_integer(expected))
+        );
+    }
+
+    #[test_case("25 50 +", VerifyError::UnavailableTile(50); "tile not in round")]
+    #[test_case("3 3 *", VerifyError::UnavailableTile(3); "tile used twice")]
+    #[test_case("3 7 -", VerifyError::NegativeIntermediateValue(Ratio::from_integer(-4)); "negative intermediate")]
+    #[test_case("7 2 / 2 *", VerifyError::FractionalIntermediateValue(Ratio::new(7, 2)); "fractional intermediate")]
+    #[test_case("3 2 ^", VerifyError::DisallowedOperation(Operation::Power); "disallowed operation")]
+    #[test_case("3 +", VerifyError::InvalidExpression(FixExpressionError::StackUnderflow(1)); "invalid expression")]
+    fn verify_invalid_solutions(input: &str, expected: VerifyError) {
+        let round = NumbersRound {
+            numbers: vec![25, 3, 7, 2, 2, 100],
+            target: 82,
+        };
+        assert_eq!(
+            verify(&round, &postfix(input), &SolverOptions::default()),
+            Err(expected)
+        );
+    }
+
+    #[test]
+    fn verify_negative_intermediate_when_allowed() {
+        let round = NumbersRound {
+            numbers: vec![25, 3, 7, 2, 2, 100],
+            target: 82,
+        };
+        let options = SolverOptions {
+            allow_negative_intermediate_values: true,
+            ..SolverOptions::default()
+        };
+        assert_eq!(
+            verify(&round, &postfix("3 7 - 25 *"), &options),
+            Ok(Ratio::from_integer(-100))
+        );
+    }
+}